@@ -0,0 +1,102 @@
+use std::fmt::{self, Write};
+
+use crate::{Circuit, Gate, Signal, Source, WireId};
+
+/// Writes the circuit as a DOT graph, labelling each wire with its current signal.
+pub fn write_dot(circuit: &Circuit, out: &mut impl Write) -> fmt::Result {
+    writeln!(out, "digraph circuit {{")?;
+    writeln!(out, "    rankdir=LR;")?;
+
+    for &id in &circuit.plan {
+        let name = circuit.names[id];
+        let signal = circuit.signals[id];
+
+        if circuit.overrides[id].is_some() {
+            writeln!(out, "    \"{name}\" [shape=box, style=bold, label=\"{name}\\n{signal}\"];")?;
+        } else {
+            writeln!(out, "    \"{name}\" [shape=box, label=\"{name}\\n{signal}\"];")?;
+        }
+    }
+
+    let mut consts = 0;
+
+    let mut write_source = |out: &mut dyn Write, source: Source<WireId>, target: &str| match source
+    {
+        Source::Const(signal) => {
+            consts += 1;
+            writeln!(out, "    \"const{consts}\" [shape=plain, label=\"{signal}\"];")?;
+            writeln!(out, "    \"const{consts}\" -> \"{target}\";")
+        }
+        Source::Wire(id) => writeln!(out, "    \"{}\" -> \"{target}\";", circuit.names[id]),
+    };
+
+    for &id in &circuit.plan {
+        let name = circuit.names[id];
+
+        let (op, lhs, rhs) = match circuit.driver(id) {
+            Gate::Ident(input) => {
+                write_source(out, input, name)?;
+                continue;
+            }
+            Gate::Not(input) => ("NOT", input, None),
+            Gate::And { lhs, rhs } => ("AND", lhs, Some(rhs)),
+            Gate::Or { lhs, rhs } => ("OR", lhs, Some(rhs)),
+            Gate::Shl { lhs, rhs } => ("LSHIFT", lhs, Some(rhs)),
+            Gate::Shr { lhs, rhs } => ("RSHIFT", lhs, Some(rhs)),
+        };
+
+        let gate = format!("gate{id}");
+
+        writeln!(out, "    \"{gate}\" [shape=ellipse, label=\"{op}\"];")?;
+        write_source(out, lhs, &gate)?;
+        if let Some(rhs) = rhs {
+            write_source(out, rhs, &gate)?;
+        }
+        writeln!(out, "    \"{gate}\" -> \"{name}\";")?;
+    }
+
+    writeln!(out, "}}")
+}
+
+/// Writes the circuit as a structural Verilog module, with every wire that drives nothing else
+/// as an output port.
+///
+/// Wire names are prefixed with `w_`, so they cannot clash with Verilog keywords.
+pub fn write_verilog(circuit: &Circuit, out: &mut impl Write) -> fmt::Result {
+    let bits = Signal::BITS;
+    let width = format!("[{}:0]", bits - 1);
+
+    let (outputs, internal): (Vec<_>, Vec<_>) =
+        circuit.plan.iter().partition(|&&id| circuit.dependents[id].is_empty());
+
+    writeln!(out, "module circuit(")?;
+    for (i, &&id) in outputs.iter().enumerate() {
+        let sep = if i + 1 < outputs.len() { "," } else { "" };
+        writeln!(out, "    output {width} w_{}{sep}", circuit.names[id])?;
+    }
+    writeln!(out, ");")?;
+
+    for &id in internal {
+        writeln!(out, "    wire {width} w_{};", circuit.names[id])?;
+    }
+
+    let source = |source: Source<WireId>| match source {
+        Source::Const(signal) => format!("{bits}'d{signal}"),
+        Source::Wire(id) => format!("w_{}", circuit.names[id]),
+    };
+
+    for &id in &circuit.plan {
+        let expr = match circuit.driver(id) {
+            Gate::Ident(input) => source(input),
+            Gate::Not(input) => format!("~{}", source(input)),
+            Gate::And { lhs, rhs } => format!("{} & {}", source(lhs), source(rhs)),
+            Gate::Or { lhs, rhs } => format!("{} | {}", source(lhs), source(rhs)),
+            Gate::Shl { lhs, rhs } => format!("{} << {}", source(lhs), source(rhs)),
+            Gate::Shr { lhs, rhs } => format!("{} >> {}", source(lhs), source(rhs)),
+        };
+
+        writeln!(out, "    assign w_{} = {expr};", circuit.names[id])?;
+    }
+
+    writeln!(out, "endmodule")
+}
@@ -1,27 +1,205 @@
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeSet, HashMap};
 use std::ops::Index;
 use std::sync::LazyLock;
 
 use anyhow::{Context, Result};
 use regex::Regex;
+use uint::UInt;
+
+mod export;
 
 const INPUT: &str = include_str!("./input");
 
-type Signal = u16;
+type Signal = UInt<16>;
 type Wire = str;
 
+type WireId = usize;
+
 #[derive(Clone, Debug, Default)]
 struct Circuit<'a> {
-    wires: HashMap<&'a Wire, Signal>,
+    names: Vec<&'a Wire>,
+    ids: HashMap<&'a Wire, WireId>,
+    drivers: Vec<Option<Gate<WireId>>>,
+    dependents: Vec<Vec<WireId>>,
+    plan: Vec<WireId>,
+    ranks: Vec<usize>,
+    overrides: Vec<Option<Signal>>,
+    signals: Vec<Signal>,
+    changed: Vec<WireId>,
 }
 
 impl<'a> Circuit<'a> {
-    fn set(&mut self, wire: &'a Wire, signal: Signal) {
-        self.wires.insert(wire, signal);
+    /// Compiles the instructions into an evaluation plan, with every wire ordered after the wires
+    /// driving it, and evaluates it.
+    fn compile(instructions: &[Instruction<'a>]) -> Result<Self> {
+        let mut circuit = Circuit::default();
+
+        for instr in instructions {
+            let output = circuit.intern(instr.output);
+            let gate = instr.gate.map(|wire| circuit.intern(wire));
+
+            if circuit.drivers[output].replace(gate).is_some() {
+                anyhow::bail!("wire '{}' is driven by multiple gates", instr.output);
+            }
+        }
+
+        let dangling = circuit
+            .drivers
+            .iter()
+            .zip(&circuit.names)
+            .filter_map(|(driver, &name)| driver.is_none().then_some(name))
+            .collect::<Vec<_>>();
+
+        if !dangling.is_empty() {
+            anyhow::bail!("wires without a driver: {}", dangling.join(", "));
+        }
+
+        let n = circuit.names.len();
+
+        circuit.dependents = vec![Vec::new(); n];
+
+        for id in 0..n {
+            for input in circuit.driver(id).inputs() {
+                circuit.dependents[input].push(id);
+            }
+        }
+
+        circuit.plan = circuit.sort()?;
+        circuit.ranks = vec![0; n];
+
+        for (rank, &id) in circuit.plan.iter().enumerate() {
+            circuit.ranks[id] = rank;
+        }
+
+        circuit.overrides = vec![None; n];
+        circuit.signals = vec![Signal::MIN; n];
+        circuit.eval();
+
+        Ok(circuit)
+    }
+
+    fn intern(&mut self, wire: &'a Wire) -> WireId {
+        *self.ids.entry(wire).or_insert_with(|| {
+            self.names.push(wire);
+            self.drivers.push(None);
+            self.names.len() - 1
+        })
+    }
+
+    fn driver(&self, id: WireId) -> Gate<WireId> {
+        self.drivers[id].expect("compiled circuit has a driver for every wire")
+    }
+
+    fn sort(&self) -> Result<Vec<WireId>> {
+        let n = self.names.len();
+
+        let mut pending = (0..n).map(|id| self.driver(id).inputs().count()).collect::<Vec<_>>();
+
+        let mut ready = (0..n).filter(|&id| pending[id] == 0).collect::<Vec<_>>();
+        let mut plan = Vec::with_capacity(n);
+
+        while let Some(id) = ready.pop() {
+            plan.push(id);
+
+            for &dependent in &self.dependents[id] {
+                pending[dependent] -= 1;
+                if pending[dependent] == 0 {
+                    ready.push(dependent);
+                }
+            }
+        }
+
+        if let Some(start) = (0..n).find(|&id| pending[id] > 0) {
+            // Every unresolved wire has an unresolved input, so following them must loop.
+            let mut path = vec![start];
+
+            loop {
+                let id = *path.last().unwrap();
+                let next = self.driver(id).inputs().find(|&input| pending[input] > 0).unwrap();
+
+                if let Some(pos) = path.iter().position(|&id| id == next) {
+                    let cycle = path[pos..]
+                        .iter()
+                        .chain([&next])
+                        .map(|&id| self.names[id])
+                        .collect::<Vec<_>>();
+
+                    anyhow::bail!("cycle between wires: {}", cycle.join(" -> "));
+                }
+
+                path.push(next);
+            }
+        }
+
+        Ok(plan)
+    }
+
+    fn eval(&mut self) {
+        for rank in 0..self.plan.len() {
+            let id = self.plan[rank];
+            self.signals[id] = self.compute(id);
+        }
+    }
+
+    fn compute(&self, id: WireId) -> Signal {
+        self.overrides[id].unwrap_or_else(|| self.driver(id).eval(&self.signals))
+    }
+
+    fn id(&self, wire: &'_ Wire) -> Result<WireId> {
+        self.ids
+            .get(wire)
+            .copied()
+            .with_context(|| format!("circuit does not contain wire: '{wire}'"))
+    }
+
+    /// Forces the signal on a wire, ignoring its driver, and re-evaluates the wires downstream.
+    fn set_override(&mut self, wire: &'_ Wire, signal: Signal) -> Result<()> {
+        let id = self.id(wire)?;
+
+        self.overrides[id] = Some(signal);
+        self.propagate(id);
+
+        Ok(())
+    }
+
+    /// Restores the driver of an overridden wire, and re-evaluates the wires downstream.
+    fn clear_override(&mut self, wire: &'_ Wire) -> Result<()> {
+        let id = self.id(wire)?;
+
+        self.overrides[id] = None;
+        self.propagate(id);
+
+        Ok(())
+    }
+
+    fn propagate(&mut self, id: WireId) {
+        self.changed.clear();
+
+        // Visiting in plan order ensures every wire is recomputed after all of its inputs.
+        let mut queue = BTreeSet::from([self.ranks[id]]);
+
+        while let Some(rank) = queue.pop_first() {
+            let id = self.plan[rank];
+            let signal = self.compute(id);
+
+            if signal == self.signals[id] {
+                continue;
+            }
+
+            self.signals[id] = signal;
+            self.changed.push(id);
+
+            queue.extend(self.dependents[id].iter().map(|&dependent| self.ranks[dependent]));
+        }
+    }
+
+    /// Returns the wires whose signal changed in the last override, in evaluation order.
+    fn changed(&self) -> impl Iterator<Item = &'a Wire> + '_ {
+        self.changed.iter().map(|&id| self.names[id])
     }
 
     fn get(&self, wire: &'_ Wire) -> Option<&Signal> {
-        self.wires.get(wire)
+        self.ids.get(wire).map(|&id| &self.signals[id])
     }
 }
 
@@ -37,88 +215,160 @@ impl Index<&'_ Wire> for Circuit<'_> {
 }
 
 #[derive(Clone, Copy, Debug)]
-enum Source<'a> {
+enum Source<W> {
     Const(Signal),
-    Wire(&'a Wire),
+    Wire(W),
+}
+
+impl<W> Source<W> {
+    fn map<U>(self, f: impl FnOnce(W) -> U) -> Source<U> {
+        match self {
+            Source::Const(signal) => Source::Const(signal),
+            Source::Wire(wire) => Source::Wire(f(wire)),
+        }
+    }
+
+    fn wire(self) -> Option<W> {
+        match self {
+            Source::Const(_) => None,
+            Source::Wire(wire) => Some(wire),
+        }
+    }
 }
 
-impl Source<'_> {
-    fn try_eval(self, circuit: &Circuit) -> Option<Signal> {
+impl Source<WireId> {
+    fn eval(self, signals: &[Signal]) -> Signal {
         match self {
-            Source::Const(signal) => Some(signal),
-            Source::Wire(wire) => circuit.get(wire).copied(),
+            Source::Const(signal) => signal,
+            Source::Wire(id) => signals[id],
         }
     }
 }
 
 #[derive(Clone, Copy, Debug)]
-enum Gate<'a> {
-    Ident(Source<'a>),
-    Not(Source<'a>),
-    And { lhs: Source<'a>, rhs: Source<'a> },
-    Or { lhs: Source<'a>, rhs: Source<'a> },
-    Shl { lhs: Source<'a>, rhs: Source<'a> },
-    Shr { lhs: Source<'a>, rhs: Source<'a> },
+enum Gate<W> {
+    Ident(Source<W>),
+    Not(Source<W>),
+    And { lhs: Source<W>, rhs: Source<W> },
+    Or { lhs: Source<W>, rhs: Source<W> },
+    Shl { lhs: Source<W>, rhs: Source<W> },
+    Shr { lhs: Source<W>, rhs: Source<W> },
 }
 
-impl Gate<'_> {
-    fn try_eval(self, circuit: &Circuit) -> Option<Signal> {
+impl<W> Gate<W> {
+    fn map<U>(self, mut f: impl FnMut(W) -> U) -> Gate<U> {
         match self {
-            Gate::Ident(input) => input.try_eval(circuit),
-            Gate::Not(input) => input.try_eval(circuit).map(|v| !v),
-            Gate::And { lhs, rhs } => Some(lhs.try_eval(circuit)? & rhs.try_eval(circuit)?),
-            Gate::Or { lhs, rhs } => Some(lhs.try_eval(circuit)? | rhs.try_eval(circuit)?),
-            Gate::Shl { lhs, rhs } => Some(lhs.try_eval(circuit)? << rhs.try_eval(circuit)?),
-            Gate::Shr { lhs, rhs } => Some(lhs.try_eval(circuit)? >> rhs.try_eval(circuit)?),
+            Gate::Ident(input) => Gate::Ident(input.map(f)),
+            Gate::Not(input) => Gate::Not(input.map(f)),
+            Gate::And { lhs, rhs } => Gate::And { lhs: lhs.map(&mut f), rhs: rhs.map(f) },
+            Gate::Or { lhs, rhs } => Gate::Or { lhs: lhs.map(&mut f), rhs: rhs.map(f) },
+            Gate::Shl { lhs, rhs } => Gate::Shl { lhs: lhs.map(&mut f), rhs: rhs.map(f) },
+            Gate::Shr { lhs, rhs } => Gate::Shr { lhs: lhs.map(&mut f), rhs: rhs.map(f) },
+        }
+    }
+
+    fn inputs(self) -> impl Iterator<Item = W> {
+        let (lhs, rhs) = match self {
+            Gate::Ident(input) | Gate::Not(input) => (input, None),
+            Gate::And { lhs, rhs }
+            | Gate::Or { lhs, rhs }
+            | Gate::Shl { lhs, rhs }
+            | Gate::Shr { lhs, rhs } => (lhs, Some(rhs)),
+        };
+
+        lhs.wire().into_iter().chain(rhs.and_then(Source::wire))
+    }
+}
+
+impl Gate<WireId> {
+    fn eval(self, signals: &[Signal]) -> Signal {
+        match self {
+            Gate::Ident(input) => input.eval(signals),
+            Gate::Not(input) => !input.eval(signals),
+            Gate::And { lhs, rhs } => lhs.eval(signals) & rhs.eval(signals),
+            Gate::Or { lhs, rhs } => lhs.eval(signals) | rhs.eval(signals),
+            Gate::Shl { lhs, rhs } => lhs.eval(signals) << rhs.eval(signals),
+            Gate::Shr { lhs, rhs } => lhs.eval(signals) >> rhs.eval(signals),
         }
     }
 }
 
 #[derive(Clone, Copy, Debug)]
 struct Instruction<'a> {
-    gate: Gate<'a>,
+    gate: Gate<&'a Wire>,
     output: &'a Wire,
 }
 
 fn main() -> Result<()> {
     let instructions = parse_input(INPUT).context("failed to parse input")?;
 
-    let a = part1(&instructions);
+    let mut args = std::env::args().skip(1);
 
-    println!("part 1: {a}");
-    println!("part 2: {}", part2(&instructions, a));
+    match args.next().as_deref() {
+        None => {
+            let a = part1(&instructions)?;
+
+            println!("part 1: {a}");
+            println!("part 2: {}", part2(&instructions, a)?);
+        }
+        Some("what-if") => {
+            let mut circuit = emulate(&instructions)?;
+
+            // Each argument is `wire=signal` to override a wire, or `wire=` to clear it again.
+            for arg in args {
+                let (wire, signal) =
+                    arg.split_once('=').with_context(|| format!("invalid override: '{arg}'"))?;
+
+                if signal.is_empty() {
+                    circuit.clear_override(wire)?;
+                } else {
+                    let signal = signal
+                        .parse()
+                        .with_context(|| format!("invalid signal for wire '{wire}': '{signal}'"))?;
+                    circuit.set_override(wire, signal)?;
+                }
+
+                let changed = circuit
+                    .changed()
+                    .map(|wire| format!("{wire}={}", circuit[wire]))
+                    .collect::<Vec<_>>();
+
+                println!("{arg}: {} changed", changed.len());
+                for line in changed.chunks(8) {
+                    println!("    {}", line.join(" "));
+                }
+            }
+        }
+        Some("dot") => {
+            let mut out = String::new();
+            export::write_dot(&emulate(&instructions)?, &mut out)?;
+            print!("{out}");
+        }
+        Some("verilog") => {
+            let mut out = String::new();
+            export::write_verilog(&emulate(&instructions)?, &mut out)?;
+            print!("{out}");
+        }
+        Some(command) => anyhow::bail!("unknown command: '{command}'"),
+    }
 
     Ok(())
 }
 
-fn part1(instructions: &[Instruction]) -> Signal {
-    emulate(instructions.to_vec().into())["a"]
+fn part1(instructions: &[Instruction]) -> Result<Signal> {
+    Ok(emulate(instructions)?["a"])
 }
 
-fn part2(instructions: &[Instruction], a: Signal) -> Signal {
-    let mut instructions = VecDeque::from(instructions.to_vec());
+fn part2(instructions: &[Instruction], a: Signal) -> Result<Signal> {
+    let mut circuit = emulate(instructions)?;
 
-    for instr in &mut instructions {
-        if instr.output == "b" {
-            instr.gate = Gate::Ident(Source::Const(a));
-        }
-    }
+    circuit.set_override("b", a)?;
 
-    emulate(instructions)["a"]
+    Ok(circuit["a"])
 }
 
-fn emulate(mut instructions: VecDeque<Instruction>) -> Circuit {
-    let mut circuit = Circuit::default();
-
-    while let Some(instr) = instructions.pop_front() {
-        if let Some(result) = instr.gate.try_eval(&circuit) {
-            circuit.set(instr.output, result);
-        } else {
-            instructions.push_back(instr);
-        }
-    }
-
-    circuit
+fn emulate<'a>(instructions: &[Instruction<'a>]) -> Result<Circuit<'a>> {
+    Circuit::compile(instructions).context("failed to compile circuit")
 }
 
 fn parse_input(input: &str) -> Result<Vec<Instruction>> {
@@ -187,20 +437,80 @@ fn parse_instruction(line: &str) -> Result<Instruction> {
 
 #[cfg(test)]
 mod example {
+    use uint::uint;
+
     const EXAMPLE: &str = include_str!("./example");
 
     #[test]
     fn emulate() {
         let instructions = super::parse_input(EXAMPLE).unwrap();
-        let wires = super::emulate(instructions.to_vec().into());
-
-        assert_eq!(wires["d"], 72);
-        assert_eq!(wires["e"], 507);
-        assert_eq!(wires["f"], 492);
-        assert_eq!(wires["g"], 114);
-        assert_eq!(wires["h"], 65412);
-        assert_eq!(wires["i"], 65079);
-        assert_eq!(wires["x"], 123);
-        assert_eq!(wires["y"], 456);
+        let wires = super::emulate(&instructions).unwrap();
+
+        assert_eq!(wires["d"], uint!(super::Signal, 72));
+        assert_eq!(wires["e"], uint!(super::Signal, 507));
+        assert_eq!(wires["f"], uint!(super::Signal, 492));
+        assert_eq!(wires["g"], uint!(super::Signal, 114));
+        assert_eq!(wires["h"], uint!(super::Signal, 65412));
+        assert_eq!(wires["i"], uint!(super::Signal, 65079));
+        assert_eq!(wires["x"], uint!(super::Signal, 123));
+        assert_eq!(wires["y"], uint!(super::Signal, 456));
+    }
+
+    #[test]
+    fn overrides() {
+        let instructions = super::parse_input(EXAMPLE).unwrap();
+        let mut wires = super::emulate(&instructions).unwrap();
+
+        wires.set_override("x", uint!(super::Signal, 0)).unwrap();
+
+        let mut changed = wires.changed().collect::<Vec<_>>();
+        changed.sort();
+
+        assert_eq!(changed, ["d", "e", "f", "h", "x"]);
+        assert_eq!(wires["d"], uint!(super::Signal, 0));
+        assert_eq!(wires["e"], uint!(super::Signal, 456));
+        assert_eq!(wires["h"], uint!(super::Signal, 65535));
+
+        wires.clear_override("x").unwrap();
+
+        assert_eq!(wires.changed().count(), 5);
+        assert_eq!(wires["d"], uint!(super::Signal, 72));
+        assert!(wires.set_override("z", uint!(super::Signal, 0)).is_err());
+    }
+
+    #[test]
+    fn export() {
+        let instructions = super::parse_input(EXAMPLE).unwrap();
+        let wires = super::emulate(&instructions).unwrap();
+
+        let mut dot = String::new();
+        super::export::write_dot(&wires, &mut dot).unwrap();
+
+        assert!(dot.contains("\"d\" [shape=box, label=\"d\\n72\"];"));
+        assert!(dot.contains("[shape=plain, label=\"123\"];"));
+
+        let mut verilog = String::new();
+        super::export::write_verilog(&wires, &mut verilog).unwrap();
+
+        assert!(verilog.contains("    output [15:0] w_d"));
+        assert!(verilog.contains("    wire [15:0] w_x;\n"));
+        assert!(verilog.contains("    assign w_x = 16'd123;\n"));
+        assert!(verilog.contains("    assign w_f = w_x << 16'd2;\n"));
+        assert!(verilog.contains("    assign w_h = ~w_x;\n"));
+    }
+
+    #[test]
+    fn errors() {
+        let cycle = super::parse_input("x AND z -> y\ny -> z\n1 -> x").unwrap();
+        let err = super::Circuit::compile(&cycle).unwrap_err();
+        assert_eq!(err.to_string(), "cycle between wires: y -> z -> y");
+
+        let duplicate = super::parse_input("1 -> x\n2 -> x").unwrap();
+        let err = super::Circuit::compile(&duplicate).unwrap_err();
+        assert_eq!(err.to_string(), "wire 'x' is driven by multiple gates");
+
+        let dangling = super::parse_input("x OR y -> z\nNOT w -> x").unwrap();
+        let err = super::Circuit::compile(&dangling).unwrap_err();
+        assert_eq!(err.to_string(), "wires without a driver: y, w");
     }
 }
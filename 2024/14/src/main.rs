@@ -1,4 +1,5 @@
 use std::collections::HashSet;
+use std::fmt;
 use std::str::FromStr;
 use std::sync::LazyLock;
 
@@ -7,8 +8,10 @@ use num::Zero;
 use regex::Regex;
 
 use self::geometry::Vec2;
+use self::tree::Detection;
 
 mod geometry;
+mod tree;
 
 const INPUT: &str = include_str!("./input");
 
@@ -49,6 +52,25 @@ impl Robot {
         self.position.x = self.position.x.rem_euclid(map.width);
         self.position.y = self.position.y.rem_euclid(map.height);
     }
+
+    /// Returns the robot after `seconds` seconds.
+    fn at(&self, seconds: i64, map: &Map) -> Robot {
+        // Only the seconds within each period matter, which keeps the products in range.
+        let along = |p: i32, v: i32, period: i32| {
+            let t = seconds.rem_euclid(i64::from(period));
+            let p = (i64::from(p) + i64::from(v) * t).rem_euclid(i64::from(period));
+
+            p as i32
+        };
+
+        Robot {
+            position: Vec2 {
+                x: along(self.position.x, self.velocity.x, map.width),
+                y: along(self.position.y, self.velocity.y, map.height),
+            },
+            velocity: self.velocity,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -102,8 +124,19 @@ fn main() -> Result<()> {
     let robots = parse_input(INPUT).context("failed to parse input")?;
     let map = Map { width: 101, height: 103 };
 
-    println!("part 1: {}", part1(&robots, &map));
-    println!("part 2: {}", part2(&robots, &map));
+    match std::env::args().nth(1).as_deref() {
+        None => {
+            println!("part 1: {}", part1(&robots, &map));
+            println!("part 2: {}", part2(&robots, &map)?);
+        }
+        Some("tree") => {
+            let detection =
+                Detection::new(&robots, &map).context("map size has a common factor")?;
+
+            print!("{detection}");
+        }
+        Some(command) => anyhow::bail!("unknown command: '{command}'"),
+    }
 
     Ok(())
 }
@@ -132,75 +165,57 @@ fn part1(robots: &[Robot], map: &Map) -> i64 {
     counts.into_iter().product()
 }
 
-fn part2(robots: &[Robot], map: &Map) -> i32 {
-    let mut robots = robots.to_vec();
-
-    // let mut stdin = std::io::stdin().lines();
-
-    for i in 0.. {
-        {
-            let positions = robots.iter().map(|r| r.position).collect::<HashSet<Vec2>>();
-
-            // Use a rudimentary density function to find likely candidates.
-            let density = positions
-                .iter()
-                .map(|pos| {
-                    (-1..1)
-                        .flat_map(|dx| (-1..1).map(move |dy| Vec2 { x: dx, y: dy }))
-                        .filter(|v| !v.is_zero())
-                        .map(|v| *pos + v)
-                        .filter(|p| positions.contains(p))
-                        .count()
-                })
-                .sum::<usize>();
-
-            if density > 200 {
-                // print_map(&robots, map);
-                // println!("seconds = {i}");
-
-                // if stdin.next().is_none() {
-                //     break;
-                // }
-
-                return i;
-            }
-        }
+fn part2(robots: &[Robot], map: &Map) -> Result<i64> {
+    let detection = Detection::new(robots, map).context("map size has a common factor")?;
 
-        for robot in &mut robots {
-            robot.step(map);
-        }
-    }
+    Ok(detection.seconds)
+}
 
-    panic!("not found");
+/// Counts the other robots in the 8 tiles around each robot.
+fn neighbors(robots: &[Robot]) -> Vec<usize> {
+    let positions = robots.iter().map(|r| r.position).collect::<HashSet<Vec2>>();
+
+    robots
+        .iter()
+        .map(|robot| {
+            (-1..=1)
+                .flat_map(|dx| (-1..=1).map(move |dy| Vec2 { x: dx, y: dy }))
+                .filter(|v| !v.is_zero())
+                .map(|v| robot.position + v)
+                .filter(|p| positions.contains(p))
+                .count()
+        })
+        .collect()
 }
 
-// fn print_map(robots: &[Robot], map: &Map) {
-//     let width = usize::try_from(map.width).unwrap();
-//     let height = usize::try_from(map.height).unwrap();
+/// Draws the number of robots on each tile, or '.' for tiles without any.
+fn write_frame(out: &mut impl fmt::Write, robots: &[Robot], map: &Map) -> fmt::Result {
+    let width = usize::try_from(map.width).unwrap_or(0);
+    let height = usize::try_from(map.height).unwrap_or(0);
 
-//     let mut grid = vec![0; width * height];
+    let mut grid = vec![0; width * height];
 
-//     for Robot { position, .. } in robots {
-//         let Ok(index) = usize::try_from(position.y * map.width + position.x) else {
-//             continue;
-//         };
+    for Robot { position, .. } in robots {
+        let Ok(index) = usize::try_from(position.y * map.width + position.x) else {
+            continue;
+        };
 
-//         grid[index] += 1;
-//     }
+        grid[index] += 1;
+    }
 
-//     println!();
+    for line in grid.chunks(width.max(1)) {
+        for &cell in line {
+            if cell > 0 {
+                write!(out, "{cell}")?;
+            } else {
+                write!(out, ".")?;
+            }
+        }
+        writeln!(out)?;
+    }
 
-//     for line in grid.chunks(width) {
-//         for &cell in line {
-//             if cell > 0 {
-//                 print!("{cell}");
-//             } else {
-//                 print!(".");
-//             }
-//         }
-//         println!();
-//     }
-// }
+    Ok(())
+}
 
 fn parse_input(input: &str) -> Result<Vec<Robot>> {
     input.lines().map(Robot::from_str).collect()
@@ -218,4 +233,75 @@ mod example {
 
         assert_eq!(super::part1(&robots, &map), 12);
     }
+
+    #[test]
+    fn neighbors() {
+        let robots = super::parse_input(EXAMPLE).unwrap();
+
+        // Robots at (x, y) for each x and y in 0..3.
+        let block = (0..9)
+            .map(|i| super::Robot { position: super::Vec2 { x: i % 3, y: i / 3 }, ..robots[0] })
+            .collect::<Vec<_>>();
+
+        assert_eq!(super::neighbors(&block), [3, 5, 3, 5, 8, 5, 3, 5, 3]);
+    }
+
+    #[test]
+    fn crt() {
+        assert_eq!(super::tree::crt(3, 11, 5, 7), Some(47));
+        assert_eq!(super::tree::crt(0, 101, 0, 103), Some(0));
+        assert_eq!(super::tree::crt(100, 101, 102, 103), Some(101 * 103 - 1));
+        assert_eq!(super::tree::crt(1, 4, 2, 6), None);
+
+        // Maps this large put the picture past `i32::MAX` seconds.
+        assert_eq!(super::tree::crt(49999, 50000, 50000, 50001), Some(50000 * 50001 - 1));
+
+        let map = super::Map { width: 50000, height: 50001 };
+        let robot = super::Robot {
+            position: super::Vec2 { x: 3, y: 4 },
+            velocity: super::Vec2 { x: -49999, y: 50000 },
+        };
+
+        assert_eq!(robot.at(50000 * 50001 - 1, &map).position, super::Vec2 { x: 2, y: 5 });
+    }
+
+    #[test]
+    fn tree() {
+        let map = super::Map { width: 11, height: 7 };
+        let seconds = 40;
+
+        // Scatter robots so that they all land in a small block after `seconds`.
+        let mut seed = 1u32;
+        let mut random = |n: i32| {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            (seed >> 16) as i32 % n
+        };
+
+        let robots = (0..30)
+            .map(|i| {
+                let velocity = super::Vec2 { x: 1 + random(10), y: 1 + random(6) };
+                let goal = super::Vec2 { x: 4 + i % 3, y: 2 + i / 3 % 3 };
+                let back = super::Robot {
+                    position: goal,
+                    velocity: super::Vec2 { x: -velocity.x, y: -velocity.y },
+                };
+
+                super::Robot { velocity, ..back.at(seconds, &map) }
+            })
+            .collect::<Vec<_>>();
+
+        let detection = super::Detection::new(&robots, &map).unwrap();
+
+        assert_eq!(detection.seconds, seconds);
+        assert!(detection.confidence() > 2.0);
+        assert!(super::neighbors(&detection.robots).iter().all(|&n| n > 0));
+
+        let frame = detection.to_string();
+        assert!(frame.contains("picture after 40 seconds"));
+        assert!(frame.ends_with(
+            "...........\n....444....\n....333....\n....333....\n...........\n...........\n"
+        ));
+
+        assert!(super::Detection::new(&robots, &super::Map { width: 12, height: 8 }).is_none());
+    }
 }
@@ -0,0 +1,135 @@
+use std::fmt;
+
+use num::integer::{ExtendedGcd, Integer};
+
+use crate::{Map, Robot};
+
+/// How the robots spread out along one axis, which repeats every `period` seconds.
+#[derive(Clone, Copy, Debug)]
+pub struct AxisFit {
+    pub period: i32,
+    /// The second within the period where the robots are closest together.
+    pub offset: i32,
+    pub variance: f64,
+    pub mean: f64,
+    /// How many standard deviations the lowest variance is below the mean.
+    pub score: f64,
+}
+
+impl AxisFit {
+    /// Finds the second with the lowest variance along an axis, given the position and velocity
+    /// of each robot on it.
+    fn new(robots: &[Robot], period: i32, axis: impl Fn(&Robot) -> (i32, i32)) -> AxisFit {
+        let n = robots.len() as f64;
+
+        let variances = (0..period)
+            .map(|t| {
+                let (sum, sum_sq) = robots.iter().fold((0.0, 0.0), |(sum, sum_sq), robot| {
+                    let (p, v) = axis(robot);
+                    let x = f64::from((p + v * t).rem_euclid(period));
+
+                    (sum + x, sum_sq + x * x)
+                });
+
+                sum_sq / n - (sum / n).powi(2)
+            })
+            .collect::<Vec<_>>();
+
+        let (offset, variance) = variances
+            .iter()
+            .copied()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map_or((0, 0.0), |(t, variance)| (t as i32, variance));
+
+        // Compare against the other seconds, so the minimum doesn't pull its own baseline down.
+        let others = variances.iter().enumerate().filter(|&(t, _)| t as i32 != offset);
+        let count = (variances.len() - 1).max(1) as f64;
+
+        let mean = others.clone().map(|(_, v)| v).sum::<f64>() / count;
+        let deviation = (others.map(|(_, v)| (v - mean).powi(2)).sum::<f64>() / count).sqrt();
+
+        let score = if deviation > 0.0 { (mean - variance) / deviation } else { 0.0 };
+
+        AxisFit { period, offset, variance, mean, score }
+    }
+}
+
+impl fmt::Display for AxisFit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "second {} of {}, variance {:.1} against {:.1} on average ({:.1} sigma)",
+            self.offset, self.period, self.variance, self.mean, self.score,
+        )
+    }
+}
+
+/// The second where the robots most likely form a picture.
+///
+/// The robots bunch up along each axis once every period of that axis, so the picture shows up
+/// where both line up, which the Chinese remainder theorem finds.
+#[derive(Clone, Debug)]
+pub struct Detection {
+    pub seconds: i64,
+    pub x: AxisFit,
+    pub y: AxisFit,
+    /// The robots positioned at `seconds`.
+    pub robots: Vec<Robot>,
+    pub map: Map,
+}
+
+impl Detection {
+    /// Returns `None` if the width and height share a factor, as then the axes can't be told
+    /// apart from each other.
+    pub fn new(robots: &[Robot], map: &Map) -> Option<Detection> {
+        let x = AxisFit::new(robots, map.width, |robot| (robot.position.x, robot.velocity.x));
+        let y = AxisFit::new(robots, map.height, |robot| (robot.position.y, robot.velocity.y));
+
+        let seconds = crt(x.offset, map.width, y.offset, map.height)?;
+        let robots = robots.iter().map(|robot| robot.at(seconds, map)).collect();
+
+        Some(Detection { seconds, x, y, robots, map: *map })
+    }
+
+    /// How strongly the picture stands out, as the lower of the scores of the axes.
+    pub fn confidence(&self) -> f64 {
+        self.x.score.min(self.y.score)
+    }
+}
+
+impl fmt::Display for Detection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "picture after {} seconds", self.seconds)?;
+        writeln!(f, "x: {}", self.x)?;
+        writeln!(f, "y: {}", self.y)?;
+        writeln!(f, "confidence: {:.1} sigma", self.confidence())?;
+        writeln!(
+            f,
+            "robots next to another: {} of {}",
+            crate::neighbors(&self.robots).iter().filter(|&&n| n > 0).count(),
+            self.robots.len(),
+        )?;
+        writeln!(f)?;
+
+        crate::write_frame(f, &self.robots, &self.map)
+    }
+}
+
+/// Finds the smallest non-negative `t` with `t = a (mod m)` and `t = b (mod n)`.
+///
+/// This works in `i64`, as `t` can be up to `m * n`.
+pub fn crt(a: i32, m: i32, b: i32, n: i32) -> Option<i64> {
+    let ExtendedGcd { gcd, x, .. } = m.extended_gcd(&n);
+
+    if gcd != 1 {
+        return None;
+    }
+
+    let (a, m, b, n, x) = (i64::from(a), i64::from(m), i64::from(b), i64::from(n), i64::from(x));
+
+    // `x` is the inverse of `m` modulo `n`, so this steps from `a` to the right residue of `n`.
+    let k = ((b - a) * x).rem_euclid(n);
+
+    Some((a + m * k).rem_euclid(m * n))
+}
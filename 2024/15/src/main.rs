@@ -1,10 +1,15 @@
 use std::collections::{HashMap, HashSet};
+use std::fmt;
 
 use anyhow::{Context, Result};
 
 use self::geometry::{Direction, Vec2};
+use self::play::Session;
+use self::warehouse::{Crate, Warehouse};
 
 mod geometry;
+mod play;
+mod warehouse;
 
 const INPUT: &str = include_str!("./input");
 
@@ -22,190 +27,149 @@ struct Map {
 }
 
 impl Map {
-    fn move_robot(&mut self, dir: Direction) -> bool {
-        let space = self.robot.adjacent(dir);
-
-        // Fast path checks for robot moving without touching boxes.
-        match self.tiles.get(&space) {
-            None | Some(Tile::Wall) => return false,
-            Some(Tile::Floor) => {
-                self.robot = space;
-                return true;
+    /// Builds the warehouse with every tile scaled up to `factor` cells, so that each box covers
+    /// that many cells, and the robot starts in the top left one.
+    fn scale(&self, factor: Vec2) -> Warehouse {
+        let width = self.tiles.keys().map(|pos| pos.x + 1).max().unwrap_or(0);
+        let height = self.tiles.keys().map(|pos| pos.y + 1).max().unwrap_or(0);
+
+        let scale = |pos: Vec2| Vec2 { x: pos.x * factor.x, y: pos.y * factor.y };
+
+        let mut walls = HashSet::new();
+        let mut crates = Vec::new();
+
+        for (&pos, tile) in &self.tiles {
+            match tile {
+                Tile::Floor => {}
+                Tile::Box => crates.push(Crate { pos: scale(pos), size: factor }),
+                Tile::Wall => walls.extend(Crate { pos: scale(pos), size: factor }.cells()),
             }
-            Some(Tile::Box) => {}
         }
 
-        // The robot will attempt to push at least one box.
-        let mut end_space = space;
+        // Keep the boxes in reading order, which doesn't matter for the puzzle but makes the
+        // warehouse easier to inspect.
+        crates.sort_by_key(|c| (c.pos.y, c.pos.x));
 
-        loop {
-            end_space = end_space.adjacent(dir);
-
-            match self.tiles.get(&end_space) {
-                None | Some(Tile::Wall) => return false,
-                Some(Tile::Floor) => break,
-                Some(Tile::Box) => continue,
-            }
-        }
-
-        // Update robot position.
-        self.robot = space;
-
-        // Apply updates to pushed boxes.
-        // We can ignore tiles in between the new spaces of the first and last box.
-        self.tiles.insert(space, Tile::Floor);
-        self.tiles.insert(end_space, Tile::Box);
-
-        true
+        Warehouse::new(width * factor.x, height * factor.y, walls, crates, scale(self.robot))
     }
+}
 
-    fn to_map2(&self) -> Map2 {
-        fn scale(v: &Vec2) -> Vec2 {
-            Vec2 { x: v.x * 2, y: v.y }
-        }
+impl fmt::Display for Map {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.tiles.keys().map(|pos| pos.x + 1).max().unwrap_or(0);
+        let height = self.tiles.keys().map(|pos| pos.y + 1).max().unwrap_or(0);
 
-        let mut tiles = HashMap::new();
+        for y in 0..height {
+            for x in 0..width {
+                let pos = Vec2 { x, y };
 
-        for (pos, tile) in &self.tiles {
-            let lpos = scale(pos);
-            let rpos = lpos.adjacent(Direction::Right);
+                let c = match self.tiles.get(&pos) {
+                    _ if pos == self.robot => '@',
+                    Some(Tile::Floor) => '.',
+                    Some(Tile::Box) => 'O',
+                    Some(Tile::Wall) => '#',
+                    None => ' ',
+                };
 
-            let (left, right) = match tile {
-                Tile::Floor => (Tile2::Floor, Tile2::Floor),
-                Tile::Box => (Tile2::BoxLeft, Tile2::BoxRight),
-                Tile::Wall => (Tile2::Wall, Tile2::Wall),
-            };
+                write!(f, "{c}")?;
+            }
 
-            tiles.insert(lpos, left);
-            tiles.insert(rpos, right);
+            writeln!(f)?;
         }
 
-        let robot = scale(&self.robot);
-
-        Map2 { tiles, robot }
+        Ok(())
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
-enum Tile2 {
-    Floor,
-    BoxLeft,
-    BoxRight,
-    Wall,
+fn parse_move(c: char) -> Option<Direction> {
+    match c {
+        '^' => Some(Direction::Up),
+        '>' => Some(Direction::Right),
+        'v' => Some(Direction::Down),
+        '<' => Some(Direction::Left),
+        _ => None,
+    }
 }
 
-#[derive(Clone, Debug)]
-struct Map2 {
-    tiles: HashMap<Vec2, Tile2>,
-    robot: Vec2,
+fn move_char(dir: Direction) -> char {
+    match dir {
+        Direction::Up => '^',
+        Direction::Right => '>',
+        Direction::Down => 'v',
+        Direction::Left => '<',
+    }
 }
 
-impl Map2 {
-    fn move_robot(&mut self, dir: Direction) -> bool {
-        let new_pos = self.robot.adjacent(dir);
+fn main() -> Result<()> {
+    let (map, moves) = parse_input(INPUT).context("failed to parse input")?;
 
-        let mut stack = Vec::new();
+    let mut args = std::env::args().skip(1);
 
-        // Fast path checks for robot moving without touching boxes.
-        match self.tiles.get(&new_pos) {
-            None | Some(Tile2::Wall) => return false,
-            Some(Tile2::Floor) => {
-                self.robot = new_pos;
-                return true;
-            }
-            Some(Tile2::BoxLeft) => {
-                stack.push((new_pos, Tile2::BoxLeft));
-                stack.push((new_pos.adjacent(Direction::Right), Tile2::BoxRight));
-            }
-            Some(Tile2::BoxRight) => {
-                stack.push((new_pos, Tile2::BoxRight));
-                stack.push((new_pos.adjacent(Direction::Left), Tile2::BoxLeft));
-            }
+    match args.next().as_deref() {
+        None => {
+            println!("part 1: {}", part1(&map, &moves));
+            println!("part 2: {}", part2(&map, &moves));
         }
+        Some("play") => {
+            let scale = match args.next() {
+                Some(scale) => parse_scale(&scale)?,
+                None => Vec2 { x: 1, y: 1 },
+            };
 
-        let mut seen = HashSet::new();
-        let mut boxes = Vec::new();
-
-        // Find and check all boxes that will be moved.
-        while let Some((pos, tile)) = stack.pop() {
-            if seen.contains(&pos) {
-                continue;
-            }
-
-            let new_pos = pos.adjacent(dir);
-
-            match self.tiles.get(&new_pos) {
-                None | Some(Tile2::Wall) => return false,
-                Some(Tile2::Floor) => {}
-                Some(Tile2::BoxLeft) => {
-                    stack.push((new_pos, Tile2::BoxLeft));
-                    stack.push((new_pos.adjacent(Direction::Right), Tile2::BoxRight));
-                }
-                Some(Tile2::BoxRight) => {
-                    stack.push((new_pos, Tile2::BoxRight));
-                    stack.push((new_pos.adjacent(Direction::Left), Tile2::BoxLeft));
-                }
-            }
-
-            seen.insert(pos);
-            boxes.push((pos, tile));
+            Session::new(&map, scale).play(std::io::stdin().lock())?;
         }
+        Some("scale") => {
+            for arg in args {
+                let scale = parse_scale(&arg)?;
 
-        // Update robot position.
-        self.robot = new_pos;
-
-        // Update boxes.
-        for (pos, _) in &mut boxes {
-            // Remove the box from the tile map.
-            self.tiles.insert(*pos, Tile2::Floor);
-
-            // Get the new position of the box.
-            *pos += dir.vector::<i32>();
+                println!("{arg}: {}", gps(&map, scale, &moves));
+            }
         }
-        for (pos, tile) in boxes {
-            // Insert the box back into the map at its new position.
-            self.tiles.insert(pos, tile);
+        Some("replay") => {
+            let path = args.next().context("missing script path")?;
+            let script = std::fs::read_to_string(&path)
+                .with_context(|| format!("failed to read script '{path}'"))?;
+            let (map, moves) = parse_input(&script).context("failed to parse script")?;
+
+            println!("part 1: {}", part1(&map, &moves));
+            println!("part 2: {}", part2(&map, &moves));
         }
-
-        true
+        Some(command) => anyhow::bail!("unknown command: '{command}'"),
     }
-}
-
-fn main() -> Result<()> {
-    let (map, moves) = parse_input(INPUT).context("failed to parse input")?;
-
-    println!("part 1: {}", part1(&map, &moves));
-    println!("part 2: {}", part2(&map, &moves));
 
     Ok(())
 }
 
 fn part1(map: &Map, moves: &[Direction]) -> i32 {
-    let mut map = map.clone();
+    gps(map, Vec2 { x: 1, y: 1 }, moves)
+}
+
+fn part2(map: &Map, moves: &[Direction]) -> i32 {
+    gps(map, Vec2 { x: 2, y: 1 }, moves)
+}
+
+/// Moves the robot around the warehouse scaled by `factor`, and sums the GPS coordinates of the
+/// boxes afterwards.
+fn gps(map: &Map, factor: Vec2, moves: &[Direction]) -> i32 {
+    let mut warehouse = map.scale(factor);
 
     for &dir in moves {
-        map.move_robot(dir);
+        warehouse.move_robot(dir);
     }
 
-    map.tiles
-        .into_iter()
-        .filter(|(_, t)| matches!(t, Tile::Box))
-        .map(|(p, _)| p.x + (p.y * 100))
-        .sum()
+    warehouse.gps()
 }
 
-fn part2(map: &Map, moves: &[Direction]) -> i32 {
-    let mut map = map.to_map2();
+fn parse_scale(s: &str) -> Result<Vec2> {
+    let (x, y) = s.split_once('x').with_context(|| format!("invalid scale: '{s}'"))?;
+    let scale = Vec2 {
+        x: x.parse().with_context(|| format!("invalid width: '{x}'"))?,
+        y: y.parse().with_context(|| format!("invalid height: '{y}'"))?,
+    };
 
-    for &dir in moves {
-        map.move_robot(dir);
-    }
+    anyhow::ensure!(scale.x > 0 && scale.y > 0, "scale must be positive: '{s}'");
 
-    map.tiles
-        .into_iter()
-        .filter(|(_, t)| matches!(t, Tile2::BoxLeft))
-        .map(|(p, _)| p.x + (p.y * 100))
-        .sum()
+    Ok(scale)
 }
 
 fn parse_input(input: &str) -> Result<(Map, Vec<Direction>)> {
@@ -241,13 +205,7 @@ fn parse_input(input: &str) -> Result<(Map, Vec<Direction>)> {
     let moves = moves_input
         .chars()
         .filter(|c| *c != '\n')
-        .map(|c| match c {
-            '^' => Ok(Direction::Up),
-            '>' => Ok(Direction::Right),
-            'v' => Ok(Direction::Down),
-            '<' => Ok(Direction::Left),
-            _ => anyhow::bail!("invalid move: '{c}'"),
-        })
+        .map(|c| parse_move(c).with_context(|| format!("invalid move: '{c}'")))
         .collect::<Result<Vec<_>>>()?;
 
     Ok((map, moves))
@@ -284,4 +242,141 @@ mod big_example {
 
         assert_eq!(super::part2(&map, &moves), 9021);
     }
+
+    #[test]
+    fn session() {
+        use super::geometry::Direction;
+        use super::play::Command;
+
+        let (map, moves) = super::parse_input(EXAMPLE).unwrap();
+        let mut session = super::Session::new(&map, super::Vec2 { x: 2, y: 1 });
+
+        let first = session.warehouse().to_string();
+
+        for &dir in &moves[..10] {
+            session.step(dir);
+        }
+        let tenth = session.warehouse().to_string();
+
+        // Wander off for a few moves and take them back again.
+        session.step(Direction::Up);
+        session.step(Direction::Left);
+        assert_eq!(session.undo(), Some(Direction::Left));
+        assert_eq!(session.undo(), Some(Direction::Up));
+        assert_eq!(session.warehouse().to_string(), tenth);
+
+        for &dir in &moves[10..] {
+            session.step(dir);
+        }
+
+        let mut script = String::new();
+        session.write_script(&mut script).unwrap();
+
+        let (replay_map, replay_moves) = super::parse_input(&script).unwrap();
+        assert_eq!(replay_map.to_string(), map.to_string());
+        assert_eq!(replay_moves, moves);
+        assert_eq!(super::part2(&replay_map, &replay_moves), 9021);
+        assert!(session.save("missing/directory/script").is_err());
+
+        while session.undo().is_some() {}
+        assert_eq!(session.warehouse().to_string(), first);
+
+        let commands = |keys: &[u8]| {
+            let mut keys = keys.iter().copied();
+            std::iter::from_fn(|| Command::read(&mut keys)).collect::<anyhow::Result<Vec<_>>>()
+        };
+
+        assert_eq!(
+            commands(b"^\x1b[Cu v\n").unwrap(),
+            [
+                Command::Move(Direction::Up),
+                Command::Move(Direction::Right),
+                Command::Undo,
+                Command::Move(Direction::Down)
+            ]
+        );
+        assert!(commands(b"^x").is_err());
+
+        // The path to save to follows the key, up to the end of the line.
+        let mut keys = b"s out.txt\nq".iter().copied();
+
+        assert_eq!(Command::read(&mut keys).unwrap().unwrap(), Command::Save);
+        assert_eq!(super::play::read_line(&mut keys), "out.txt");
+        assert_eq!(Command::read(&mut keys).unwrap().unwrap(), Command::Quit);
+        assert!(Command::read(&mut keys).is_none());
+    }
+}
+
+#[cfg(test)]
+mod scaled {
+    use super::geometry::Direction::{Down, Left, Right, Up};
+    use super::Vec2;
+
+    const EXAMPLE: &str = include_str!("./big_example");
+
+    fn positions(warehouse: &super::Warehouse) -> Vec<(i32, i32)> {
+        warehouse.crates().iter().map(|c| (c.pos.x, c.pos.y)).collect()
+    }
+
+    #[test]
+    fn wide() {
+        let (map, _) =
+            super::parse_input("#######\n#.....#\n#.OO@.#\n#.....#\n#######\n\n<").unwrap();
+        let mut warehouse = map.scale(Vec2 { x: 3, y: 1 });
+
+        assert_eq!(positions(&warehouse), [(6, 2), (9, 2)]);
+
+        for _ in 0..3 {
+            assert!(warehouse.move_robot(Left));
+        }
+        assert!(!warehouse.move_robot(Left));
+        assert_eq!(positions(&warehouse), [(3, 2), (6, 2)]);
+        assert_eq!(warehouse.to_string().lines().nth(2), Some("###[=][=]@........###"));
+    }
+
+    #[test]
+    fn tall() {
+        let (map, _) =
+            super::parse_input("######\n#....#\n#@O..#\n#.O..#\n#....#\n######\n\n>").unwrap();
+        let mut warehouse = map.scale(Vec2 { x: 1, y: 2 });
+
+        assert_eq!(positions(&warehouse), [(2, 4), (2, 6)]);
+
+        // Pushing from beside the lower half of a box only moves that box.
+        for dir in [Down, Right, Left, Down, Right] {
+            assert!(warehouse.move_robot(dir));
+        }
+        assert_eq!(positions(&warehouse), [(3, 4), (3, 6)]);
+
+        // Pushing the bottom box up takes the one stacked on top of it along too.
+        for dir in [Down, Down, Right, Up, Up] {
+            assert!(warehouse.move_robot(dir));
+        }
+        assert!(!warehouse.move_robot(Up));
+        assert_eq!(positions(&warehouse), [(3, 2), (3, 4)]);
+        assert_eq!(warehouse.gps(), 606);
+    }
+
+    #[test]
+    fn factors() {
+        let (map, moves) = super::parse_input(EXAMPLE).unwrap();
+
+        assert_eq!(super::gps(&map, Vec2 { x: 1, y: 1 }, &moves), 10092);
+        assert_eq!(super::gps(&map, Vec2 { x: 2, y: 1 }, &moves), 9021);
+
+        // The boxes have to stay clear of the walls and of each other at any scale.
+        for factor in [Vec2 { x: 3, y: 1 }, Vec2 { x: 1, y: 2 }, Vec2 { x: 2, y: 3 }] {
+            let mut warehouse = map.scale(factor);
+
+            for &dir in &moves {
+                warehouse.move_robot(dir);
+            }
+
+            let cells = warehouse.crates().iter().flat_map(|c| c.cells()).collect::<Vec<_>>();
+            let unique = cells.iter().collect::<std::collections::HashSet<_>>();
+
+            assert_eq!(cells.len(), unique.len());
+            assert_eq!(cells.len() as i32, 21 * factor.x * factor.y);
+        }
+    }
 }
@@ -0,0 +1,272 @@
+use std::fmt::{self, Write};
+use std::io::{self, BufRead, Read, Write as _};
+use std::process::{self, Stdio};
+
+use anyhow::{Context, Result};
+
+use crate::geometry::{Direction, Vec2};
+use crate::warehouse::Warehouse;
+use crate::Map;
+
+/// The number of moves on each line of a saved script, the same as in the puzzle input.
+const SCRIPT_WIDTH: usize = 1000;
+
+/// The keys of the play mode, shown when it starts.
+const HELP: &str = "move with the arrow keys or ^>v<, u to undo, s to save the moves, q to quit";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+    Move(Direction),
+    Undo,
+    /// Saves the moves as a script, to a path read after the key.
+    Save,
+    Quit,
+}
+
+impl Command {
+    /// Reads the next command from the keys pressed, skipping whitespace, or returns `None` once
+    /// there are no keys left. Moves are given as `^>v<` or arrow keys, `u` undoes, `s` saves and
+    /// `q` quits.
+    pub fn read(keys: &mut impl Iterator<Item = u8>) -> Option<Result<Command>> {
+        loop {
+            let command = match keys.next()? {
+                b'u' => Command::Undo,
+                b's' => Command::Save,
+                // Ctrl-C and Ctrl-D arrive as keys too while the terminal is unbuffered.
+                b'q' | b'\x03' | b'\x04' => Command::Quit,
+                // Arrow keys arrive as the escape sequences `ESC [ A` through `ESC [ D`.
+                b'\x1b' => match (keys.next(), keys.next()) {
+                    (Some(b'['), Some(b'A')) => Command::Move(Direction::Up),
+                    (Some(b'['), Some(b'B')) => Command::Move(Direction::Down),
+                    (Some(b'['), Some(b'C')) => Command::Move(Direction::Right),
+                    (Some(b'['), Some(b'D')) => Command::Move(Direction::Left),
+                    _ => return Some(Err(anyhow::anyhow!("unknown escape sequence"))),
+                },
+                key => match crate::parse_move(char::from(key)) {
+                    Some(dir) => Command::Move(dir),
+                    None if key.is_ascii_whitespace() => continue,
+                    None => {
+                        return Some(Err(anyhow::anyhow!("invalid key: '{}'", key.escape_ascii())))
+                    }
+                },
+            };
+
+            return Some(Ok(command));
+        }
+    }
+}
+
+/// Reads keys up to the end of the line, as text without surrounding whitespace.
+pub fn read_line(keys: &mut impl Iterator<Item = u8>) -> String {
+    let line = keys.take_while(|&key| key != b'\n' && key != b'\r').collect::<Vec<_>>();
+
+    String::from_utf8_lossy(&line).trim().to_string()
+}
+
+/// The bytes of `input`, stopping at the first read error, which is kept to be reported later.
+struct Keys<R> {
+    bytes: io::Bytes<R>,
+    error: Option<io::Error>,
+}
+
+impl<R: Read> Iterator for Keys<R> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        if self.error.is_some() {
+            return None;
+        }
+
+        match self.bytes.next()? {
+            Ok(key) => Some(key),
+            Err(err) => {
+                self.error = Some(err);
+                None
+            }
+        }
+    }
+}
+
+/// Switches the terminal on stdin to pass on keys as they are pressed, without echoing them,
+/// until it is dropped. Does nothing if stdin isn't a terminal.
+struct Terminal {
+    /// The settings to restore, as saved by `stty -g`.
+    saved: Option<String>,
+}
+
+impl Terminal {
+    fn unbuffered() -> Terminal {
+        let terminal = Terminal { saved: stty(&["-g"]) };
+        terminal.set_unbuffered();
+
+        terminal
+    }
+
+    fn is_unbuffered(&self) -> bool {
+        self.saved.is_some()
+    }
+
+    /// Runs `f` with the terminal back in its usual line-buffered mode, echoing what is typed.
+    fn buffered<T>(&self, f: impl FnOnce() -> T) -> T {
+        self.restore();
+        let result = f();
+        self.set_unbuffered();
+
+        result
+    }
+
+    fn set_unbuffered(&self) {
+        if self.is_unbuffered() {
+            stty(&["-icanon", "-echo", "-isig", "min", "1"]);
+        }
+    }
+
+    fn restore(&self) {
+        if let Some(saved) = &self.saved {
+            stty(&[saved]);
+        }
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        self.restore();
+    }
+}
+
+/// Runs `stty` on stdin, returning its output if it succeeded.
+fn stty(args: &[&str]) -> Option<String> {
+    let output = process::Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// A game of moving the robot by hand, which remembers every move so that they can be undone or
+/// saved as a script for the puzzle solutions to replay.
+#[derive(Clone, Debug)]
+pub struct Session {
+    start: Map,
+    warehouse: Warehouse,
+    /// Each move made, along with the boxes it pushed, or `None` if it was blocked.
+    history: Vec<(Direction, Option<Vec<usize>>)>,
+}
+
+impl Session {
+    /// Starts a session on the warehouse in `start`, scaled up by `factor`.
+    pub fn new(start: &Map, factor: Vec2) -> Session {
+        Session { start: start.clone(), warehouse: start.scale(factor), history: Vec::new() }
+    }
+
+    pub fn warehouse(&self) -> &Warehouse {
+        &self.warehouse
+    }
+
+    pub fn moves(&self) -> impl Iterator<Item = Direction> + '_ {
+        self.history.iter().map(|&(dir, _)| dir)
+    }
+
+    /// Moves the robot, returning whether it was able to. The move is recorded even if it is
+    /// blocked, as the puzzle moves can be too.
+    pub fn step(&mut self, dir: Direction) -> bool {
+        let pushed = self.warehouse.push(dir);
+        let moved = pushed.is_some();
+
+        self.history.push((dir, pushed));
+
+        moved
+    }
+
+    /// Undoes the last move, returning it if there was one.
+    pub fn undo(&mut self) -> Option<Direction> {
+        let (dir, pushed) = self.history.pop()?;
+
+        if let Some(pushed) = pushed {
+            self.warehouse.unpush(dir, &pushed);
+        }
+
+        Some(dir)
+    }
+
+    /// Writes the starting map and the moves made in the same format as the puzzle input.
+    pub fn write_script(&self, out: &mut impl Write) -> fmt::Result {
+        writeln!(out, "{}", self.start)?;
+
+        let moves = self.moves().map(crate::move_char).collect::<Vec<_>>();
+
+        for line in moves.chunks(SCRIPT_WIDTH) {
+            writeln!(out, "{}", line.iter().collect::<String>())?;
+        }
+
+        Ok(())
+    }
+
+    /// Saves the script of the session to `path`.
+    pub fn save(&self, path: &str) -> Result<()> {
+        let mut script = String::new();
+        self.write_script(&mut script)?;
+
+        std::fs::write(path, script).with_context(|| format!("failed to save script to '{path}'"))
+    }
+
+    /// Plays the session with keys read from `input`, rendering the warehouse after every move.
+    ///
+    /// On a terminal each key takes effect as soon as it is pressed. Piped input can hold any
+    /// number of keys per line.
+    pub fn play(&mut self, input: impl BufRead) -> Result<()> {
+        let terminal = Terminal::unbuffered();
+        let mut keys = Keys { bytes: input.bytes(), error: None };
+
+        if !terminal.is_unbuffered() {
+            println!("{HELP}");
+        }
+        self.render(&terminal);
+
+        while let Some(command) = Command::read(&mut keys) {
+            match command {
+                Ok(Command::Move(dir)) => {
+                    self.step(dir);
+                    self.render(&terminal);
+                }
+                Ok(Command::Undo) => match self.undo() {
+                    Some(_) => self.render(&terminal),
+                    None => println!("nothing to undo"),
+                },
+                Ok(Command::Save) => {
+                    print!("save to: ");
+                    io::stdout().flush().context("failed to write prompt")?;
+
+                    let path = terminal.buffered(|| read_line(&mut keys));
+
+                    // A failed save keeps the session going, so the moves aren't lost.
+                    match self.save(&path) {
+                        Ok(()) => println!("saved {} moves to '{path}'", self.history.len()),
+                        Err(err) => println!("{err:#}"),
+                    }
+                }
+                Ok(Command::Quit) => return Ok(()),
+                Err(err) => println!("{err}"),
+            }
+        }
+
+        match keys.error {
+            Some(err) => Err(err).context("failed to read keys"),
+            None => Ok(()),
+        }
+    }
+
+    /// Shows the warehouse and the number of moves, redrawing the screen on a terminal.
+    fn render(&self, terminal: &Terminal) {
+        if terminal.is_unbuffered() {
+            print!("\x1b[H\x1b[2J");
+            println!("{HELP}");
+        }
+
+        println!("{}", self.warehouse());
+        println!("moves: {}", self.history.len());
+    }
+}
@@ -0,0 +1,166 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use num::Zero;
+
+use crate::geometry::{Direction, Vec2};
+
+/// A box covering a `size` rectangle of cells, with its top left corner at `pos`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Crate {
+    pub pos: Vec2,
+    pub size: Vec2,
+}
+
+impl Crate {
+    pub fn cells(self) -> impl Iterator<Item = Vec2> {
+        (0..self.size.y).flat_map(move |dy| {
+            (0..self.size.x).map(move |dx| Vec2 { x: self.pos.x + dx, y: self.pos.y + dy })
+        })
+    }
+
+    /// The GPS coordinate of the box, which is measured to its top left corner.
+    pub fn gps(self) -> i32 {
+        self.pos.x + self.pos.y * 100
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Warehouse {
+    width: i32,
+    height: i32,
+    walls: HashSet<Vec2>,
+    crates: Vec<Crate>,
+    /// The box covering each cell, as an index into `crates`.
+    cells: HashMap<Vec2, usize>,
+    robot: Vec2,
+}
+
+impl Warehouse {
+    pub fn new(
+        width: i32,
+        height: i32,
+        walls: HashSet<Vec2>,
+        crates: Vec<Crate>,
+        robot: Vec2,
+    ) -> Warehouse {
+        let cells = crates
+            .iter()
+            .enumerate()
+            .flat_map(|(i, c)| c.cells().map(move |cell| (cell, i)))
+            .collect();
+
+        Warehouse { width, height, walls, crates, cells, robot }
+    }
+
+    pub fn crates(&self) -> &[Crate] {
+        &self.crates
+    }
+
+    fn is_blocked(&self, pos: Vec2) -> bool {
+        let inside = (0..self.width).contains(&pos.x) && (0..self.height).contains(&pos.y);
+
+        !inside || self.walls.contains(&pos)
+    }
+
+    /// Moves the robot, pushing every box in its way, unless one of them would hit a wall.
+    pub fn move_robot(&mut self, dir: Direction) -> bool {
+        self.push(dir).is_some()
+    }
+
+    /// Moves the robot like [`Warehouse::move_robot`], returning the indices of the boxes it
+    /// pushed, or `None` if it was blocked.
+    pub fn push(&mut self, dir: Direction) -> Option<Vec<usize>> {
+        let space = self.robot.adjacent(dir);
+
+        let mut stack = vec![space];
+        let mut seen = HashSet::new();
+        let mut pushed = Vec::new();
+
+        // Find and check all boxes that will be moved, which are those touching the robot or
+        // another pushed box on the side it is moving towards.
+        while let Some(pos) = stack.pop() {
+            if self.is_blocked(pos) {
+                return None;
+            }
+
+            let Some(&i) = self.cells.get(&pos) else { continue };
+
+            if !seen.insert(i) {
+                continue;
+            }
+
+            pushed.push(i);
+            stack.extend(
+                self.crates[i]
+                    .cells()
+                    .map(|cell| cell.adjacent(dir))
+                    .filter(|cell| self.cells.get(cell) != Some(&i)),
+            );
+        }
+
+        self.robot = space;
+        self.shift(&pushed, dir.vector());
+
+        Some(pushed)
+    }
+
+    /// Takes back a move in `dir` that pushed the boxes in `pushed`.
+    pub fn unpush(&mut self, dir: Direction, pushed: &[usize]) {
+        let offset = Vec2::<i32>::zero() - dir.vector::<i32>();
+
+        self.robot += offset;
+        self.shift(pushed, offset);
+    }
+
+    /// Moves the boxes by `offset`, removing all of them before adding them back so they don't
+    /// overwrite each other.
+    fn shift(&mut self, crates: &[usize], offset: Vec2) {
+        for &i in crates {
+            for cell in self.crates[i].cells() {
+                self.cells.remove(&cell);
+            }
+        }
+        for &i in crates {
+            self.crates[i].pos += offset;
+            self.cells.extend(self.crates[i].cells().map(|cell| (cell, i)));
+        }
+    }
+
+    pub fn gps(&self) -> i32 {
+        self.crates().iter().map(|c| c.gps()).sum()
+    }
+}
+
+impl fmt::Display for Warehouse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let pos = Vec2 { x, y };
+
+                let c = if pos == self.robot {
+                    '@'
+                } else if self.walls.contains(&pos) {
+                    '#'
+                } else if let Some(&i) = self.cells.get(&pos) {
+                    let Crate { pos: start, size } = self.crates[i];
+
+                    match pos.x - start.x {
+                        _ if size.x == 1 => 'O',
+                        0 => '[',
+                        dx if dx == size.x - 1 => ']',
+                        _ => '=',
+                    }
+                } else {
+                    '.'
+                };
+
+                write!(f, "{c}")?;
+            }
+
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
 use std::cmp::Ordering;
 use std::collections::hash_map::Entry;
 use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::num::NonZeroU32;
 use std::str::FromStr;
 
 use anyhow::{Context, Error, Result};
@@ -19,9 +20,57 @@ struct Node {
     dir: Direction,
 }
 
-impl Node {
-    fn from_start(start: Vec2) -> Node {
-        Node { pos: start, dir: Direction::East }
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Move {
+    Forward,
+    TurnLeft,
+    TurnRight,
+    TurnAround,
+}
+
+impl Move {
+    fn apply(self, node: Node) -> Node {
+        match self {
+            Move::Forward => Node { pos: node.pos.adjacent(node.dir), dir: node.dir },
+            Move::TurnLeft => Node { pos: node.pos, dir: node.dir.turn_left() },
+            Move::TurnRight => Node { pos: node.pos, dir: node.dir.turn_right() },
+            Move::TurnAround => Node { pos: node.pos, dir: node.dir.turn_around() },
+        }
+    }
+}
+
+/// The costs of each move, and the heading at the start.
+///
+/// Turns with no cost are not allowed. Costs are never zero, as the search relies on every move
+/// making a path more expensive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Rules {
+    heading: Direction,
+    forward: NonZeroU32,
+    turn: Option<NonZeroU32>,
+    turn_around: Option<NonZeroU32>,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules {
+            heading: Direction::East,
+            forward: NonZeroU32::MIN,
+            turn: NonZeroU32::new(1000),
+            turn_around: None,
+        }
+    }
+}
+
+impl Rules {
+    fn cost(&self, mv: Move) -> Option<u32> {
+        let cost = match mv {
+            Move::Forward => Some(self.forward),
+            Move::TurnLeft | Move::TurnRight => self.turn,
+            Move::TurnAround => self.turn_around,
+        };
+
+        cost.map(NonZeroU32::get)
     }
 }
 
@@ -34,22 +83,204 @@ enum Tile {
 #[derive(Clone, Debug)]
 struct Map {
     tiles: HashMap<Vec2, Tile>,
-    start: Node,
+    start: Vec2,
     goal: Vec2,
 }
 
 impl Map {
-    fn edges(&self, node: &Node) -> impl Iterator<Item = (i32, Node)> {
-        let forward = node.pos.adjacent(node.dir);
+    fn edges(&self, node: Node, rules: Rules) -> impl Iterator<Item = (u32, Move, Node)> + '_ {
+        [Move::Forward, Move::TurnLeft, Move::TurnRight, Move::TurnAround].into_iter().filter_map(
+            move |mv| {
+                let next = mv.apply(node);
+
+                match self.tiles.get(&next.pos) {
+                    Some(Tile::Floor) => Some((rules.cost(mv)?, mv, next)),
+                    _ => None,
+                }
+            },
+        )
+    }
+
+    /// Follows the moves from the start, returning the total cost if they are allowed and end at
+    /// the goal.
+    fn replay(&self, rules: Rules, moves: &[Move]) -> Option<u32> {
+        let mut node = Node { pos: self.start, dir: rules.heading };
+        let mut total = 0;
+
+        for &mv in moves {
+            let next = mv.apply(node);
+
+            if self.tiles.get(&next.pos) != Some(&Tile::Floor) {
+                return None;
+            }
+
+            total += rules.cost(mv)?;
+            node = next;
+        }
+
+        (node.pos == self.goal).then_some(total)
+    }
+
+    /// Renders the map with the tiles visited by the moves marked by the heading on them.
+    fn render(&self, rules: Rules, moves: &[Move]) -> String {
+        let mut marks = HashMap::new();
+        let mut node = Node { pos: self.start, dir: rules.heading };
+
+        for &mv in moves {
+            node = mv.apply(node);
+            marks.insert(node.pos, node.dir);
+        }
+
+        let max_x = self.tiles.keys().map(|pos| pos.x).max().unwrap_or(0);
+        let max_y = self.tiles.keys().map(|pos| pos.y).max().unwrap_or(0);
+
+        let mut buf = String::new();
+
+        for y in 0..=max_y {
+            for x in 0..=max_x {
+                let pos = Vec2 { x, y };
+
+                buf.push(match (self.tiles.get(&pos), marks.get(&pos)) {
+                    _ if pos == self.start => 'S',
+                    _ if pos == self.goal => 'E',
+                    (_, Some(Direction::North)) => '^',
+                    (_, Some(Direction::East)) => '>',
+                    (_, Some(Direction::South)) => 'v',
+                    (_, Some(Direction::West)) => '<',
+                    (Some(Tile::Wall), None) => '#',
+                    _ => '.',
+                });
+            }
+            buf.push('\n');
+        }
+
+        buf
+    }
+
+    fn solve(&self, rules: Rules) -> Option<Solution> {
+        let start = Node { pos: self.start, dir: rules.heading };
+
+        let mut queue = BinaryHeap::new();
+        let mut costs = HashMap::new();
+        let mut parents = HashMap::<Node, Vec<(Move, Node)>>::new();
+
+        let mut best = None;
+        let mut ends = Vec::new();
+
+        costs.insert(start, 0);
+        queue.push(MinScored(0, start));
+
+        while let Some(MinScored(node_cost, node)) = queue.pop() {
+            if best.is_some_and(|best| node_cost > best) {
+                break;
+            }
+            if costs.get(&node).is_some_and(|&cost| cost < node_cost) {
+                // Ignore stale entries for nodes that have since been reached more cheaply.
+                continue;
+            }
+
+            if node.pos == self.goal {
+                best = Some(node_cost);
+                ends.push(node);
+                continue;
+            }
+
+            for (cost, mv, next) in self.edges(node, rules) {
+                let next_cost = node_cost + cost;
+
+                match costs.entry(next) {
+                    Entry::Occupied(entry) => {
+                        let cost = entry.into_mut();
+                        match next_cost.cmp(cost) {
+                            // Ignore nodes that have been reached though a shorter path.
+                            Ordering::Greater => continue,
+                            Ordering::Less => {
+                                parents.insert(next, vec![(mv, node)]);
+                            }
+                            Ordering::Equal => {
+                                parents.entry(next).or_default().push((mv, node));
+                                continue;
+                            }
+                        }
+                        *cost = next_cost;
+                    }
+                    Entry::Vacant(entry) => {
+                        entry.insert(next_cost);
+                        parents.insert(next, vec![(mv, node)]);
+                    }
+                }
+
+                queue.push(MinScored(next_cost, next));
+            }
+        }
+
+        Some(Solution { cost: best?, start, ends, parents })
+    }
+}
+
+/// Every optimal path from the start to the goal, stored as the optimal parents of each node.
+#[derive(Clone, Debug)]
+struct Solution {
+    cost: u32,
+    start: Node,
+    ends: Vec<Node>,
+    parents: HashMap<Node, Vec<(Move, Node)>>,
+}
+
+impl Solution {
+    fn tiles(&self) -> HashSet<Vec2> {
+        let mut stack = self.ends.clone();
+        let mut seen = HashSet::new();
+
+        while let Some(node) = stack.pop() {
+            if !seen.insert(node) {
+                continue;
+            }
+
+            if let Some(parents) = self.parents.get(&node) {
+                stack.extend(parents.iter().map(|&(_, parent)| parent));
+            }
+        }
 
-        matches!(self.tiles.get(&forward), Some(Tile::Floor))
-            .then(|| (1, forward, node.dir))
-            .into_iter()
-            .chain([
-                (1000, node.pos, node.dir.turn_left()),
-                (1000, node.pos, node.dir.turn_right()),
-            ])
-            .map(|(cost, pos, dir)| (cost, Node { pos, dir }))
+        seen.into_iter().map(|node| node.pos).collect()
+    }
+
+    /// Lazily enumerates the moves of every optimal path.
+    fn paths(&self) -> impl Iterator<Item = Vec<Move>> + '_ {
+        let mut ends = self.ends.iter();
+
+        // Walks back from each end, keeping the index of the next parent to try for each node, and
+        // the move from each parent into the node above it.
+        let mut stack = Vec::<(Node, usize)>::new();
+        let mut moves = Vec::new();
+
+        std::iter::from_fn(move || loop {
+            let Some(&(node, i)) = stack.last() else {
+                stack.push((*ends.next()?, 0));
+                continue;
+            };
+
+            if node == self.start {
+                let path = moves.iter().rev().copied().collect();
+
+                stack.pop();
+                moves.pop();
+
+                return Some(path);
+            }
+
+            match self.parents.get(&node).and_then(|parents| parents.get(i)) {
+                Some(&(mv, parent)) => {
+                    stack.last_mut().unwrap().1 += 1;
+                    stack.push((parent, 0));
+                    moves.push(mv);
+                }
+                None => {
+                    stack.pop();
+                    moves.pop();
+                }
+            }
+        })
     }
 }
 
@@ -89,8 +320,6 @@ impl FromStr for Map {
         let start = start.context("no start found")?;
         let end = end.context("no end found")?;
 
-        let start = Node::from_start(start);
-
         Ok(Map { tiles, start, goal: end })
     }
 }
@@ -98,116 +327,35 @@ impl FromStr for Map {
 fn main() -> Result<()> {
     let map = INPUT.parse::<Map>().context("failed to parse input")?;
 
-    println!("part 1: {}", part1(&map));
-    println!("part 2: {}", part2(&map));
-
-    Ok(())
-}
-
-fn part1(map: &Map) -> i32 {
-    let mut queue = BinaryHeap::new();
-    let mut costs = HashMap::new();
-
-    costs.insert(map.start, 0);
-    queue.push(MinScored(0, map.start));
-
-    while let Some(MinScored(node_cost, node)) = queue.pop() {
-        if node.pos == map.goal {
-            return node_cost;
+    match std::env::args().nth(1).as_deref() {
+        None => {
+            println!("part 1: {}", part1(&map));
+            println!("part 2: {}", part2(&map));
         }
+        Some("path") => {
+            let rules = Rules::default();
+            let solution = map.solve(rules).context("no path found")?;
+            let moves = solution.paths().next().context("no path found")?;
 
-        for (cost, next) in map.edges(&node) {
-            let next_cost = node_cost + cost;
-
-            match costs.entry(next) {
-                Entry::Occupied(entry) => {
-                    let cost = entry.into_mut();
-                    if *cost <= next_cost {
-                        // Ignore nodes that have been reached though a shorter path.
-                        continue;
-                    }
-                    *cost = next_cost;
-                }
-                Entry::Vacant(entry) => {
-                    entry.insert(next_cost);
-                }
-            }
+            let cost = map.replay(rules, &moves).context("path does not reach the goal")?;
 
-            queue.push(MinScored(next_cost, next));
+            print!("{}", map.render(rules, &moves));
+            println!("cost: {cost}");
+            println!("moves: {moves:?}");
+            println!("optimal paths: {}", solution.paths().count());
         }
+        Some(command) => anyhow::bail!("unknown command: '{command}'"),
     }
 
-    panic!("no path found");
+    Ok(())
 }
 
-fn part2(map: &Map) -> usize {
-    let mut queue = BinaryHeap::new();
-    let mut costs = HashMap::new();
-    let mut paths = HashMap::new();
-
-    costs.insert(map.start, 0);
-    queue.push(MinScored(0, map.start));
-
-    let mut ends = HashSet::new();
-
-    while let Some(MinScored(node_cost, node)) = queue.pop() {
-        if node.pos == map.goal {
-            let min_cost = node_cost;
-
-            while let Some(MinScored(cost, node)) = queue.pop() {
-                if cost != min_cost {
-                    break;
-                }
-                if node.pos == map.goal {
-                    ends.insert(node);
-                }
-            }
-
-            break;
-        }
-
-        for (cost, next) in map.edges(&node) {
-            let next_cost = node_cost + cost;
-
-            match costs.entry(next) {
-                Entry::Occupied(entry) => {
-                    let cost = entry.into_mut();
-                    match next_cost.cmp(cost) {
-                        // Ignore nodes that have been reached though a shorter path.
-                        Ordering::Greater => continue,
-                        Ordering::Less => {
-                            paths.insert(next, HashSet::from([node]));
-                        }
-                        Ordering::Equal => {
-                            paths.entry(next).or_default().insert(node);
-                        }
-                    }
-                    *cost = next_cost;
-                }
-                Entry::Vacant(entry) => {
-                    entry.insert(next_cost);
-                    paths.insert(next, HashSet::from([node]));
-                }
-            }
-
-            queue.push(MinScored(next_cost, next));
-        }
-    }
-
-    let mut stack = Vec::from_iter(ends);
-    let mut seen = HashSet::new();
-
-    while let Some(node) = stack.pop() {
-        if !seen.insert(node) {
-            continue;
-        }
-
-        if let Some(prev) = paths.get(&node) {
-            stack.extend(prev);
-        }
-    }
+fn part1(map: &Map) -> u32 {
+    map.solve(Rules::default()).expect("no path found").cost
+}
 
-    seen.into_iter().map(|node| node.pos).collect::<HashSet<_>>().len()
+fn part2(map: &Map) -> usize {
+    map.solve(Rules::default()).expect("no path found").tiles().len()
 }
 
 #[cfg(test)]
@@ -227,6 +375,33 @@ mod example1 {
 
         assert_eq!(super::part2(&map), 45);
     }
+
+    #[test]
+    fn paths() {
+        use std::num::NonZeroU32;
+
+        use super::{Direction, Rules};
+
+        let map = EXAMPLE.parse::<super::Map>().unwrap();
+
+        let rules = Rules::default();
+        let solution = map.solve(rules).unwrap();
+        let paths = solution.paths().collect::<Vec<_>>();
+
+        assert_eq!(paths.len(), 3);
+        assert!(paths.iter().all(|moves| map.replay(rules, moves) == Some(7036)));
+
+        let rules = Rules { heading: Direction::West, turn_around: NonZeroU32::new(1000), ..rules };
+        let solution = map.solve(rules).unwrap();
+
+        assert_eq!(solution.cost, 7036);
+        assert_eq!(solution.paths().count(), 3);
+        assert!(solution.paths().all(|moves| moves[0] == super::Move::TurnRight));
+
+        let rules = Rules { turn: NonZeroU32::new(1), ..Rules::default() };
+
+        assert_eq!(map.solve(rules).unwrap().cost, 38);
+    }
 }
 
 #[cfg(test)]
@@ -4,12 +4,14 @@ use std::sync::LazyLock;
 
 use anyhow::{Context, Result};
 use regex::Regex;
+use uint::{uint, UInt};
 use z3::ast::Ast;
 
-#[macro_use]
-mod uint3;
+mod symbolic;
+mod value;
 
-use self::uint3::u3;
+use self::symbolic::Bounds;
+use self::value::{Condition, Value};
 
 const INPUT: &str = include_str!("./input");
 
@@ -25,78 +27,114 @@ enum Opcode {
     Cdv = 7,
 }
 
+#[allow(non_camel_case_types)]
+type u3 = UInt<3>;
 type Int = u64;
 
+impl From<u3> for Opcode {
+    fn from(v: u3) -> Opcode {
+        match v.to_u64() {
+            0 => Opcode::Adv,
+            1 => Opcode::Bxl,
+            2 => Opcode::Bst,
+            3 => Opcode::Jnz,
+            4 => Opcode::Bxc,
+            5 => Opcode::Out,
+            6 => Opcode::Bdv,
+            7 => Opcode::Cdv,
+            _ => unreachable!(),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
-struct Cpu {
-    reg_a: Int,
-    reg_b: Int,
-    reg_c: Int,
+struct Cpu<V = Int> {
+    reg_a: V,
+    reg_b: V,
+    reg_c: V,
     pc: usize,
 }
 
-impl Cpu {
-    fn combo_operand(&self, operand: u3) -> Int {
-        match operand {
-            v if v == u3!(4) => self.reg_a,
-            v if v == u3!(5) => self.reg_b,
-            v if v == u3!(6) => self.reg_c,
-            v if v == u3!(7) => panic!("reserved combo operand: {v:#x}"),
-            v => Int::from(v.to_u8()),
-        }
-    }
+#[derive(Clone, Debug)]
+enum Step<V: Value> {
+    Continue,
+    Output(V),
+    Fork { zero: V::Bool, target: usize },
+    Halt,
+}
 
-    fn combo_operand_u3(&self, operand: u3) -> u3 {
+impl<V: Value> Cpu<V> {
+    fn combo_operand(&self, operand: u3) -> V {
         match operand {
-            v if v == u3!(4) => u3::from_u8(self.reg_a as u8),
-            v if v == u3!(5) => u3::from_u8(self.reg_b as u8),
-            v if v == u3!(6) => u3::from_u8(self.reg_c as u8),
-            v if v == u3!(7) => panic!("reserved combo operand: {v:#x}"),
-            v => v,
+            v if v == uint!(u3, 4) => self.reg_a.clone(),
+            v if v == uint!(u3, 5) => self.reg_b.clone(),
+            v if v == uint!(u3, 6) => self.reg_c.clone(),
+            v if v == uint!(u3, 7) => panic!("reserved combo operand: {v:#x}"),
+            v => self.reg_a.lift(v.to_u64()),
         }
     }
 
-    fn run(&mut self, rom: &[u3]) -> Vec<u3> {
-        let mut output = Vec::new();
+    /// Executes a single instruction.
+    ///
+    /// A `jnz` whose condition cannot be decided leaves the program counter on the next
+    /// instruction and returns [`Step::Fork`] with the jump target.
+    fn step(&mut self, rom: &[u3]) -> Step<V> {
+        let Some(opcode) = rom.get(self.pc) else { return Step::Halt };
+        let opcode = Opcode::from(*opcode);
+        let operand = rom[self.pc + 1];
 
-        while let Some(opcode) = rom.get(self.pc) {
-            let opcode = opcode.to_opcode();
-            let operand = rom[self.pc + 1];
+        self.pc += 2;
 
-            self.pc += 2;
+        match opcode {
+            Opcode::Adv => {
+                self.reg_a = self.reg_a.shr(&self.combo_operand(operand));
+            }
+            Opcode::Bxl => {
+                self.reg_b = self.reg_b.xor(&self.reg_b.lift(operand.to_u64()));
+            }
+            Opcode::Bst => {
+                self.reg_b = self.combo_operand(operand).low3();
+            }
+            Opcode::Jnz => {
+                let zero = self.reg_a.is_zero();
+                let target = operand.to_u64() as usize;
 
-            match opcode {
-                Opcode::Adv => {
-                    self.reg_a >>= self.combo_operand(operand);
-                }
-                Opcode::Bxl => {
-                    self.reg_b ^= Int::from(operand.to_u8());
-                }
-                Opcode::Bst => {
-                    self.reg_b = Int::from(self.combo_operand_u3(operand).to_u8());
-                }
-                Opcode::Jnz => {
-                    if self.reg_a == 0 {
-                        continue;
-                    }
-                    self.pc = usize::from(operand.to_u8());
-                }
-                Opcode::Bxc => {
-                    self.reg_b ^= self.reg_c;
-                }
-                Opcode::Out => {
-                    output.push(self.combo_operand_u3(operand));
-                }
-                Opcode::Bdv => {
-                    self.reg_b = self.reg_a >> self.combo_operand(operand);
-                }
-                Opcode::Cdv => {
-                    self.reg_c = self.reg_a >> self.combo_operand(operand);
+                match zero.decide() {
+                    Some(true) => {}
+                    Some(false) => self.pc = target,
+                    None => return Step::Fork { zero, target },
                 }
             }
+            Opcode::Bxc => {
+                self.reg_b = self.reg_b.xor(&self.reg_c);
+            }
+            Opcode::Out => {
+                return Step::Output(self.combo_operand(operand).low3());
+            }
+            Opcode::Bdv => {
+                self.reg_b = self.reg_a.shr(&self.combo_operand(operand));
+            }
+            Opcode::Cdv => {
+                self.reg_c = self.reg_a.shr(&self.combo_operand(operand));
+            }
         }
 
-        output
+        Step::Continue
+    }
+}
+
+impl Cpu {
+    fn run(&mut self, rom: &[u3]) -> Vec<u3> {
+        let mut output = Vec::new();
+
+        loop {
+            match self.step(rom) {
+                Step::Continue => {}
+                Step::Output(v) => output.push(u3::from_u64(v)),
+                Step::Fork { .. } => unreachable!("concrete values always decide branches"),
+                Step::Halt => return output,
+            }
+        }
     }
 }
 
@@ -114,87 +152,38 @@ fn part1(mut cpu: Cpu, rom: &[u3]) -> String {
 }
 
 fn part2(cpu: Cpu, rom: &[u3]) -> Int {
-    use z3::ast::BV;
+    use z3::ast::{Bool, BV};
 
     let ctx = z3::Context::new(&z3::Config::new());
-    let opt = z3::Optimize::new(&ctx);
+    let solver = z3::Solver::new(&ctx);
 
     let s = BV::new_const(&ctx, "a", 64);
 
-    let mut a = s.clone();
-    let mut b = BV::from_u64(&ctx, cpu.reg_b, 64);
-    let mut c = BV::from_u64(&ctx, cpu.reg_c, 64);
-
-    let mut pc = 0;
-
-    macro_rules! combo_operand {
-        ($operand:expr) => {
-            match $operand {
-                v if v == u3!(4) => a.clone(),
-                v if v == u3!(5) => b.clone(),
-                v if v == u3!(6) => c.clone(),
-                v if v == u3!(7) => panic!("reserved combo operand: {v:#x}"),
-                v => BV::from_u64(&ctx, v.to_u8().into(), 64),
-            }
-        };
-    }
-
-    let mut next_out = rom.iter().copied().peekable();
-
-    while let Some(opcode) = rom.get(pc) {
-        let opcode = opcode.to_opcode();
-        let operand = rom[pc + 1];
-
-        pc += 2;
+    let cpu = cpu.with_symbolic_a(&s);
+    let bounds = Bounds { max_steps: rom.len() * (rom.len() + 1), max_output: rom.len() };
 
-        match opcode {
-            Opcode::Adv => {
-                a = a.bvlshr(&combo_operand!(operand));
-            }
-            Opcode::Bxl => {
-                b ^= BV::from_u64(&ctx, operand.to_u8().into(), 64);
-            }
-            Opcode::Bst => {
-                b = combo_operand!(operand) & BV::from_u64(&ctx, 7, 64);
-            }
-            Opcode::Jnz => {
-                if next_out.peek().is_none() {
-                    opt.assert(&a._eq(&BV::from_u64(&ctx, 0, 64)));
-                    break;
-                }
-                pc = usize::from(operand.to_u8());
-            }
-            Opcode::Bxc => {
-                b ^= &c;
-            }
-            Opcode::Out => {
-                let expected = next_out.next().expect("no more rom");
-                let out = combo_operand!(operand) & BV::from_u64(&ctx, 7, 64);
+    let paths = symbolic::explore(&solver, cpu, rom, bounds);
 
-                opt.assert(&out._eq(&BV::from_u64(&ctx, expected.to_u8().into(), 64)));
-            }
-            Opcode::Bdv => {
-                b = a.bvlshr(&combo_operand!(operand));
-            }
-            Opcode::Cdv => {
-                c = a.bvlshr(&combo_operand!(operand));
-            }
+    let a = symbolic::minimize(&paths, &s, |output| {
+        if output.len() != rom.len() {
+            return None;
         }
-    }
-
-    opt.minimize(&s);
 
-    assert_eq!(next_out.next(), None);
-    assert_eq!(opt.check(&[]), z3::SatResult::Sat);
+        let eqs = output
+            .iter()
+            .zip(rom)
+            .map(|(out, expected)| out._eq(&out.lift(expected.to_u64())))
+            .collect::<Vec<_>>();
 
-    let a = opt.get_model().unwrap().eval(&s, true).unwrap().as_u64().unwrap();
+        Some(Bool::and(&ctx, &eqs.iter().collect::<Vec<_>>()))
+    });
 
-    a
+    a.expect("no initial value of register A outputs the program")
 }
 
 fn render_values(values: impl IntoIterator<Item = u3>) -> String {
     let mut buf = String::new();
-    let mut values = values.into_iter().map(|v| v.to_u8());
+    let mut values = values.into_iter().map(|v| v.to_u64());
 
     let Some(first) = values.next() else { return buf };
 
@@ -255,6 +244,31 @@ mod example1 {
 
         assert_eq!(super::part1(cpu, &rom), "4,6,3,5,6,3,5,2,1,0");
     }
+
+    #[test]
+    fn symbolic() {
+        use z3::ast::{Ast, BV};
+
+        use crate::symbolic::{self, Bounds};
+        use crate::value::Value;
+
+        let (cpu, rom) = super::parse_input(EXAMPLE).unwrap();
+
+        let ctx = z3::Context::new(&z3::Config::new());
+        let solver = z3::Solver::new(&ctx);
+        let a = BV::new_const(&ctx, "a", 64);
+
+        let bounds = Bounds { max_steps: 100, max_output: 5 };
+        let paths = symbolic::explore(&solver, cpu.with_symbolic_a(&a), &rom, bounds);
+
+        let min = symbolic::minimize(&paths, &a, |output| match output {
+            [first, _, _] => Some(first._eq(&first.lift(3))),
+            _ => None,
+        });
+
+        assert_eq!(min, Some(6));
+        assert_eq!(super::part1(super::Cpu { reg_a: 6, ..cpu }, &rom), "3,1,0");
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,118 @@
+use z3::ast::{Ast, Bool, BV};
+use z3::{Optimize, SatResult, Solver};
+
+use crate::{u3, Cpu, Int, Step};
+
+#[derive(Clone, Copy, Debug)]
+pub struct Bounds {
+    pub max_steps: usize,
+    pub max_output: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct Path<'ctx> {
+    pub cpu: Cpu<BV<'ctx>>,
+    pub condition: Vec<Bool<'ctx>>,
+    pub output: Vec<BV<'ctx>>,
+    steps: usize,
+}
+
+impl Cpu {
+    /// Lifts the CPU into the context of `reg_a`, which replaces register A.
+    pub fn with_symbolic_a<'ctx>(self, reg_a: &BV<'ctx>) -> Cpu<BV<'ctx>> {
+        Cpu {
+            reg_a: reg_a.clone(),
+            reg_b: BV::from_u64(reg_a.get_ctx(), self.reg_b, reg_a.get_size()),
+            reg_c: BV::from_u64(reg_a.get_ctx(), self.reg_c, reg_a.get_size()),
+            pc: self.pc,
+        }
+    }
+}
+
+impl<'ctx> Path<'ctx> {
+    fn is_feasible(&self, solver: &Solver<'ctx>) -> bool {
+        solver.check_assumptions(&self.condition) != SatResult::Unsat
+    }
+}
+
+/// Explores every feasible path through `rom`, forking at each undecided `jnz`.
+///
+/// Returns the paths that halt within `bounds`; paths exceeding them are dropped.
+pub fn explore<'ctx>(
+    solver: &Solver<'ctx>,
+    cpu: Cpu<BV<'ctx>>,
+    rom: &[u3],
+    bounds: Bounds,
+) -> Vec<Path<'ctx>> {
+    let mut halted = Vec::new();
+    let mut pending = vec![Path { cpu, condition: Vec::new(), output: Vec::new(), steps: 0 }];
+
+    'paths: while let Some(mut path) = pending.pop() {
+        while path.steps < bounds.max_steps {
+            path.steps += 1;
+
+            match path.cpu.step(rom) {
+                Step::Continue => {}
+                Step::Output(v) => {
+                    if path.output.len() == bounds.max_output {
+                        continue 'paths;
+                    }
+                    path.output.push(v);
+                }
+                Step::Fork { zero, target } => {
+                    let mut taken = path.clone();
+                    taken.cpu.pc = target;
+                    taken.condition.push(zero.not());
+
+                    if taken.is_feasible(solver) {
+                        pending.push(taken);
+                    }
+
+                    path.condition.push(zero);
+
+                    if !path.is_feasible(solver) {
+                        continue 'paths;
+                    }
+                }
+                Step::Halt => {
+                    halted.push(path);
+                    continue 'paths;
+                }
+            }
+        }
+    }
+
+    halted
+}
+
+/// Finds the smallest model of `var` across `paths`, where the output of the path satisfies
+/// `constraint`.
+///
+/// The constraint returns `None` for outputs that can never satisfy it, such as outputs of the
+/// wrong length.
+pub fn minimize<'ctx>(
+    paths: &[Path<'ctx>],
+    var: &BV<'ctx>,
+    constraint: impl Fn(&[BV<'ctx>]) -> Option<Bool<'ctx>>,
+) -> Option<Int> {
+    paths
+        .iter()
+        .filter_map(|path| {
+            let constraint = constraint(&path.output)?;
+
+            let opt = Optimize::new(var.get_ctx());
+
+            for cond in &path.condition {
+                opt.assert(cond);
+            }
+            opt.assert(&constraint);
+            opt.minimize(var);
+
+            if opt.check(&[]) != SatResult::Sat {
+                return None;
+            }
+
+            opt.get_model()?.eval(var, true)?.as_u64()
+        })
+        .min()
+}
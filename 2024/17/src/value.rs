@@ -0,0 +1,82 @@
+use z3::ast::{Ast, Bool, BV};
+
+use crate::Int;
+
+/// Register value the [`Cpu`](crate::Cpu) can operate on.
+pub trait Value: Clone {
+    type Bool: Condition;
+
+    fn lift(&self, n: Int) -> Self;
+
+    fn shr(&self, rhs: &Self) -> Self;
+
+    fn xor(&self, rhs: &Self) -> Self;
+
+    fn low3(&self) -> Self;
+
+    fn is_zero(&self) -> Self::Bool;
+}
+
+pub trait Condition: Clone {
+    fn decide(&self) -> Option<bool>;
+}
+
+impl Value for Int {
+    type Bool = bool;
+
+    fn lift(&self, n: Int) -> Self {
+        n
+    }
+
+    fn shr(&self, rhs: &Self) -> Self {
+        u32::try_from(*rhs).ok().and_then(|rhs| self.checked_shr(rhs)).unwrap_or(0)
+    }
+
+    fn xor(&self, rhs: &Self) -> Self {
+        self ^ rhs
+    }
+
+    fn low3(&self) -> Self {
+        self & 7
+    }
+
+    fn is_zero(&self) -> Self::Bool {
+        *self == 0
+    }
+}
+
+impl Condition for bool {
+    fn decide(&self) -> Option<bool> {
+        Some(*self)
+    }
+}
+
+impl<'ctx> Value for BV<'ctx> {
+    type Bool = Bool<'ctx>;
+
+    fn lift(&self, n: Int) -> Self {
+        BV::from_u64(self.get_ctx(), n, self.get_size())
+    }
+
+    fn shr(&self, rhs: &Self) -> Self {
+        self.bvlshr(rhs)
+    }
+
+    fn xor(&self, rhs: &Self) -> Self {
+        self.bvxor(rhs)
+    }
+
+    fn low3(&self) -> Self {
+        self.bvand(&self.lift(7))
+    }
+
+    fn is_zero(&self) -> Self::Bool {
+        self._eq(&self.lift(0)).simplify()
+    }
+}
+
+impl<'ctx> Condition for Bool<'ctx> {
+    fn decide(&self) -> Option<bool> {
+        self.as_bool()
+    }
+}
@@ -0,0 +1,43 @@
+/// A union-find structure over the indices `0..len`, with path halving and union by rank.
+#[derive(Clone, Debug)]
+pub struct DisjointSet {
+    parents: Vec<usize>,
+    ranks: Vec<u8>,
+}
+
+impl DisjointSet {
+    pub fn new(len: usize) -> DisjointSet {
+        DisjointSet { parents: (0..len).collect(), ranks: vec![0; len] }
+    }
+
+    pub fn find(&mut self, mut index: usize) -> usize {
+        while self.parents[index] != index {
+            self.parents[index] = self.parents[self.parents[index]];
+            index = self.parents[index];
+        }
+
+        index
+    }
+
+    pub fn union(&mut self, a: usize, b: usize) {
+        let a = self.find(a);
+        let b = self.find(b);
+
+        if a == b {
+            return;
+        }
+
+        match self.ranks[a].cmp(&self.ranks[b]) {
+            std::cmp::Ordering::Less => self.parents[a] = b,
+            std::cmp::Ordering::Greater => self.parents[b] = a,
+            std::cmp::Ordering::Equal => {
+                self.parents[b] = a;
+                self.ranks[a] += 1;
+            }
+        }
+    }
+
+    pub fn connected(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+}
@@ -1,12 +1,16 @@
 use std::collections::hash_map::Entry;
-use std::collections::{BinaryHeap, HashMap};
+use std::collections::{BinaryHeap, HashMap, VecDeque};
 use std::fmt;
-use std::ops::{Add, Index, IndexMut};
+use std::ops::{Add, Deref, Index, IndexMut};
+use std::str::FromStr;
+use std::time::Instant;
 
 use anyhow::{Context, Result};
 
+use self::disjoint_set::DisjointSet;
 use self::score::MinScored;
 
+mod disjoint_set;
 mod score;
 
 const INPUT: &str = include_str!("./input");
@@ -29,6 +33,19 @@ impl fmt::Display for Vec2 {
     }
 }
 
+impl FromStr for Vec2 {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (x, y) = s.split_once(',').context("missing ',' in coordinate")?;
+
+        let x = x.trim().parse().context("invalid x coordinate")?;
+        let y = y.trim().parse().context("invalid y coordinate")?;
+
+        Ok(Vec2 { x, y })
+    }
+}
+
 impl Add<(isize, isize)> for Vec2 {
     type Output = Option<Vec2>;
 
@@ -55,23 +72,30 @@ enum Cell {
 struct Grid {
     width: usize,
     height: usize,
+    start: Vec2,
+    goal: Vec2,
     cells: Vec<Cell>,
 }
 
 impl Grid {
+    /// Creates an empty grid, with the start in the top left corner and the goal in the bottom
+    /// right one.
     fn new(width: usize, height: usize) -> Grid {
         assert_ne!(width, 0, "width should be non-zero");
         assert_ne!(height, 0, "height should be non-zero");
 
-        Grid { width, height, cells: vec![Cell::Safe; width * height] }
+        let start = Vec2 { x: 0, y: 0 };
+        let goal = Vec2 { x: width - 1, y: height - 1 };
+
+        Grid { width, height, start, goal, cells: vec![Cell::Safe; width * height] }
     }
 
     const fn start(&self) -> Vec2 {
-        Vec2 { x: 0, y: 0 }
+        self.start
     }
 
     const fn goal(&self) -> Vec2 {
-        Vec2 { x: self.width - 1, y: self.height - 1 }
+        self.goal
     }
 
     const fn try_index(&self, pos: Vec2) -> Option<usize> {
@@ -172,6 +196,37 @@ impl Grid {
 
         None
     }
+
+    /// Checks whether the goal can be reached from the start, without tracking the path.
+    fn is_reachable(&self) -> bool {
+        let start = self.start();
+        let goal = self.goal();
+
+        if !matches!(self.get(start), Some(Cell::Safe)) {
+            return false;
+        }
+
+        let mut seen = vec![false; self.cells.len()];
+        let mut queue = VecDeque::from([start]);
+
+        seen[self.index(start)] = true;
+
+        while let Some(node) = queue.pop_front() {
+            if node == goal {
+                return true;
+            }
+
+            for next in self.edges(node) {
+                let index = self.index(next);
+                if !seen[index] {
+                    seen[index] = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        false
+    }
 }
 
 impl Index<Vec2> for Grid {
@@ -216,36 +271,81 @@ impl fmt::Display for Grid {
     }
 }
 
-fn main() -> Result<()> {
-    let bytes = parse_input(INPUT).context("failed to parse input")?;
-    let grid = Grid::new(71, 71);
+/// The falling bytes, along with the line of the input that the first one is on.
+#[derive(Clone, Debug)]
+struct Bytes {
+    first_line: usize,
+    positions: Vec<Vec2>,
+}
 
-    println!("part 1: {}", part1(grid.clone(), &bytes, 1024));
-    println!("part 2: {}", part2(grid, &bytes, 1024));
+impl Bytes {
+    /// Checks that every byte lands inside the grid.
+    fn check(&self, grid: &Grid) -> Result<()> {
+        for (line, &pos) in (self.first_line..).zip(&self.positions) {
+            anyhow::ensure!(
+                grid.try_index(pos).is_some(),
+                "line {line}: byte {pos} is outside of the {}x{} grid",
+                grid.width,
+                grid.height,
+            );
+        }
 
-    Ok(())
+        Ok(())
+    }
 }
 
-fn part1(mut grid: Grid, bytes: &[Vec2], dropped: usize) -> usize {
-    let mut bytes = bytes.iter().copied();
+impl Deref for Bytes {
+    type Target = [Vec2];
 
-    for byte in bytes.by_ref().take(dropped) {
-        grid[byte] = Cell::Corrupted;
+    fn deref(&self) -> &Self::Target {
+        &self.positions
     }
+}
 
-    grid.shortest_path().and_then(|p| p.len().checked_sub(1)).expect("no path found")
+/// How to find the first byte that cuts the start off from the goal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Strategy {
+    /// Drops bytes one by one, searching for a new path whenever a byte lands on the current one.
+    AStar,
+    /// Binary searches the number of dropped bytes, checking reachability for each guess.
+    BinarySearch,
+    /// Drops every byte, then removes them in reverse while joining the safe cells they leave
+    /// behind, until the start and goal end up in the same component.
+    UnionFind,
 }
 
-fn part2(mut grid: Grid, bytes: &[Vec2], dropped: usize) -> Vec2 {
-    let mut bytes = bytes.iter().copied();
+impl Strategy {
+    const ALL: [Strategy; 3] = [Strategy::AStar, Strategy::BinarySearch, Strategy::UnionFind];
 
-    for byte in bytes.by_ref().take(dropped) {
-        grid[byte] = Cell::Corrupted;
+    /// Returns the index in `bytes` of the first byte after which the goal is unreachable, or
+    /// `None` if it stays reachable, or was never reachable to begin with.
+    fn first_blocking(self, grid: Grid, bytes: &[Vec2]) -> Option<usize> {
+        if !grid.is_reachable() {
+            return None;
+        }
+
+        match self {
+            Strategy::AStar => first_blocking_astar(grid, bytes),
+            Strategy::BinarySearch => first_blocking_binary_search(grid, bytes),
+            Strategy::UnionFind => first_blocking_union_find(grid, bytes),
+        }
     }
+}
 
-    let mut path = grid.shortest_path().expect("no path found to begin with");
+impl fmt::Display for Strategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Strategy::AStar => "A*",
+            Strategy::BinarySearch => "binary search",
+            Strategy::UnionFind => "union-find",
+        })
+    }
+}
 
-    for byte in bytes {
+fn first_blocking_astar(mut grid: Grid, bytes: &[Vec2]) -> Option<usize> {
+    let mut path = grid.shortest_path()?;
+
+    for (i, &byte) in bytes.iter().enumerate() {
         grid[byte] = Cell::Corrupted;
 
         if !path.contains(&byte) {
@@ -254,25 +354,257 @@ fn part2(mut grid: Grid, bytes: &[Vec2], dropped: usize) -> Vec2 {
 
         path = match grid.shortest_path() {
             Some(path) => path,
-            None => return byte,
+            None => return Some(i),
         };
     }
 
-    panic!("no coordinates found");
+    None
 }
 
-fn parse_input(input: &str) -> Result<Vec<Vec2>> {
-    input
-        .lines()
-        .map(|pos| {
-            let (x, y) = pos.split_once(',').context("missing ',' in coordinate")?;
+fn first_blocking_binary_search(grid: Grid, bytes: &[Vec2]) -> Option<usize> {
+    let is_reachable = |dropped: usize| {
+        let mut grid = grid.clone();
 
-            let x = x.parse().context("invalid x coordinate")?;
-            let y = y.parse().context("invalid y coordinate")?;
+        for &byte in &bytes[..dropped] {
+            grid[byte] = Cell::Corrupted;
+        }
 
-            Ok(Vec2 { x, y })
-        })
-        .collect()
+        grid.is_reachable()
+    };
+
+    // Search for the number of dropped bytes at which the goal first becomes unreachable, which
+    // is always in `lo..=hi`, with `hi` past the end if it never does.
+    let mut lo = 0;
+    let mut hi = bytes.len() + 1;
+
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+
+        if is_reachable(mid) {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+
+    lo.checked_sub(1).filter(|_| lo <= bytes.len())
+}
+
+fn first_blocking_union_find(mut grid: Grid, bytes: &[Vec2]) -> Option<usize> {
+    // Bytes may land on the same cell more than once, or on one that was already corrupted, which
+    // then only becomes safe again once its first byte is removed.
+    let mut drops =
+        grid.cells.iter().map(|&cell| u32::from(cell == Cell::Corrupted)).collect::<Vec<_>>();
+
+    for &byte in bytes {
+        grid[byte] = Cell::Corrupted;
+        drops[grid.index(byte)] += 1;
+    }
+
+    let mut components = DisjointSet::new(grid.cells.len());
+
+    let join = |grid: &Grid, components: &mut DisjointSet, pos: Vec2| {
+        for next in grid.edges(pos) {
+            components.union(grid.index(pos), grid.index(next));
+        }
+    };
+
+    for y in 0..grid.height {
+        for x in 0..grid.width {
+            let pos = Vec2 { x, y };
+            if grid[pos] == Cell::Safe {
+                join(&grid, &mut components, pos);
+            }
+        }
+    }
+
+    let start = grid.index(grid.start());
+    let goal = grid.index(grid.goal());
+
+    let is_reachable = |grid: &Grid, components: &mut DisjointSet| {
+        grid[grid.start()] == Cell::Safe && components.connected(start, goal)
+    };
+
+    if is_reachable(&grid, &mut components) {
+        return None;
+    }
+
+    for (i, &byte) in bytes.iter().enumerate().rev() {
+        let index = grid.index(byte);
+
+        drops[index] -= 1;
+        if drops[index] > 0 {
+            continue;
+        }
+
+        grid[byte] = Cell::Safe;
+        join(&grid, &mut components, byte);
+
+        if is_reachable(&grid, &mut components) {
+            return Some(i);
+        }
+    }
+
+    None
+}
+
+/// The puzzle parameters, which can be given in a header at the top of the input, or on the
+/// command line as `key=value` options.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Config {
+    width: usize,
+    height: usize,
+    dropped: usize,
+    start: Option<Vec2>,
+    goal: Option<Vec2>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config { width: 71, height: 71, dropped: 1024, start: None, goal: None }
+    }
+}
+
+impl Config {
+    /// Sets an option from a `key=value` pair, where the keys are `size` (as `WxH`, or a single
+    /// number for a square grid), `dropped`, `start` and `goal` (both as `x,y`).
+    fn set(&mut self, option: &str) -> Result<()> {
+        let (key, value) = option.split_once('=').context("missing '=' in option")?;
+        let value = value.trim();
+
+        match key.trim() {
+            "size" => {
+                let (width, height) = value.split_once('x').unwrap_or((value, value));
+
+                self.width = width.trim().parse().context("invalid grid width")?;
+                self.height = height.trim().parse().context("invalid grid height")?;
+            }
+            "dropped" => self.dropped = value.parse().context("invalid number of dropped bytes")?,
+            "start" => self.start = Some(value.parse().context("invalid start")?),
+            "goal" => self.goal = Some(value.parse().context("invalid goal")?),
+            key => anyhow::bail!("unknown option: '{key}'"),
+        }
+
+        Ok(())
+    }
+
+    fn grid(&self) -> Result<Grid> {
+        anyhow::ensure!(self.width > 0 && self.height > 0, "grid size should be non-zero");
+
+        let mut grid = Grid::new(self.width, self.height);
+
+        for (corner, pos) in [(&mut grid.start, self.start), (&mut grid.goal, self.goal)] {
+            if let Some(pos) = pos {
+                *corner = pos;
+            }
+        }
+
+        for (name, pos) in [("start", grid.start), ("goal", grid.goal)] {
+            anyhow::ensure!(grid.try_index(pos).is_some(), "{name} {pos} is outside of the grid");
+        }
+
+        Ok(grid)
+    }
+}
+
+fn main() -> Result<()> {
+    let mut command = None;
+    let mut options = Vec::new();
+    let mut input = None;
+
+    for arg in std::env::args().skip(1) {
+        if let Some(path) = arg.strip_prefix("input=") {
+            let contents = std::fs::read_to_string(path)
+                .with_context(|| format!("failed to read input from '{path}'"))?;
+            input = Some(contents);
+        } else if arg.contains('=') {
+            options.push(arg);
+        } else if command.is_none() {
+            command = Some(arg);
+        } else {
+            anyhow::bail!("unexpected argument: '{arg}'");
+        }
+    }
+
+    let mut config = Config::default();
+    let bytes = parse_input(input.as_deref().unwrap_or(INPUT), &mut config)
+        .context("failed to parse input")?;
+
+    // Options on the command line take precedence over the input header.
+    for option in &options {
+        config.set(option).with_context(|| format!("invalid option '{option}'"))?;
+    }
+
+    let grid = config.grid()?;
+    bytes.check(&grid)?;
+
+    match command.as_deref() {
+        None => {
+            println!("part 1: {}", part1(grid.clone(), &bytes, config.dropped));
+            println!("part 2: {}", part2(grid, &bytes, config.dropped, Strategy::UnionFind));
+        }
+        Some("compare") => {
+            for strategy in Strategy::ALL {
+                let start = Instant::now();
+                let byte = part2(grid.clone(), &bytes, config.dropped, strategy);
+
+                println!("{strategy}: {byte} in {:?}", start.elapsed());
+            }
+        }
+        Some(command) => anyhow::bail!("unknown command: '{command}'"),
+    }
+
+    Ok(())
+}
+
+fn part1(mut grid: Grid, bytes: &[Vec2], dropped: usize) -> usize {
+    let mut bytes = bytes.iter().copied();
+
+    for byte in bytes.by_ref().take(dropped) {
+        grid[byte] = Cell::Corrupted;
+    }
+
+    grid.shortest_path().and_then(|p| p.len().checked_sub(1)).expect("no path found")
+}
+
+fn part2(mut grid: Grid, bytes: &[Vec2], dropped: usize, strategy: Strategy) -> Vec2 {
+    let (initial, bytes) = bytes.split_at(dropped.min(bytes.len()));
+
+    for &byte in initial {
+        grid[byte] = Cell::Corrupted;
+    }
+
+    assert!(grid.is_reachable(), "no path found to begin with");
+
+    let index = strategy.first_blocking(grid, bytes).expect("no coordinates found");
+
+    bytes[index]
+}
+
+/// Parses the falling bytes, after an optional header of `key=value` options for `config`
+/// followed by an empty line.
+fn parse_input(input: &str, config: &mut Config) -> Result<Bytes> {
+    let mut lines = input.lines().peekable();
+    let mut first_line = 1;
+
+    if lines.peek().is_some_and(|line| line.contains('=')) {
+        for line in lines.by_ref() {
+            first_line += 1;
+
+            if line.is_empty() {
+                break;
+            }
+
+            config.set(line).with_context(|| format!("invalid option '{line}'"))?;
+        }
+    }
+
+    let positions = (first_line..)
+        .zip(lines)
+        .map(|(n, line)| line.parse().with_context(|| format!("line {n}: invalid byte '{line}'")))
+        .collect::<Result<_>>()?;
+
+    Ok(Bytes { first_line, positions })
 }
 
 #[cfg(test)]
@@ -281,17 +613,114 @@ mod example {
 
     #[test]
     fn part1() {
-        let bytes = super::parse_input(EXAMPLE).unwrap();
-        let grid = super::Grid::new(7, 7);
+        let mut config = super::Config::default();
+        let bytes = super::parse_input(EXAMPLE, &mut config).unwrap();
+        let grid = config.grid().unwrap();
 
-        assert_eq!(super::part1(grid, &bytes, 12), 22);
+        assert_eq!(super::part1(grid, &bytes, config.dropped), 22);
     }
 
     #[test]
     fn part2() {
-        let bytes = super::parse_input(EXAMPLE).unwrap();
-        let grid = super::Grid::new(7, 7);
+        let mut config = super::Config::default();
+        let bytes = super::parse_input(EXAMPLE, &mut config).unwrap();
+        let grid = config.grid().unwrap();
+
+        for strategy in super::Strategy::ALL {
+            assert_eq!(
+                super::part2(grid.clone(), &bytes, config.dropped, strategy),
+                (6, 1),
+                "{strategy}"
+            );
+        }
+    }
+
+    #[test]
+    fn first_blocking() {
+        let mut config = super::Config::default();
+        let bytes = super::parse_input(EXAMPLE, &mut config).unwrap();
+        let grid = config.grid().unwrap();
+
+        // A byte landing on an already corrupted cell should not unblock it when removed.
+        let mut repeated = bytes.to_vec();
+        repeated.insert(10, bytes[0]);
+
+        for strategy in super::Strategy::ALL {
+            assert_eq!(strategy.first_blocking(grid.clone(), &bytes), Some(20), "{strategy}");
+            assert_eq!(strategy.first_blocking(grid.clone(), &repeated), Some(21), "{strategy}");
+            assert_eq!(strategy.first_blocking(grid.clone(), &bytes[..20]), None, "{strategy}");
+        }
+    }
+
+    #[test]
+    fn unreachable() {
+        let mut config = super::Config::default();
+        let bytes = super::parse_input(EXAMPLE, &mut config).unwrap();
+
+        // The start is corrupted before any bytes fall, and the first byte lands on it again.
+        let mut grid = config.grid().unwrap();
+        let start = grid.start();
+        grid[start] = super::Cell::Corrupted;
+
+        let mut repeated = bytes.to_vec();
+        repeated.insert(0, start);
+
+        // The start is walled in before any bytes fall.
+        let mut walled = config.grid().unwrap();
+        walled[super::Vec2 { x: 1, y: 0 }] = super::Cell::Corrupted;
+        walled[super::Vec2 { x: 0, y: 1 }] = super::Cell::Corrupted;
+
+        for strategy in super::Strategy::ALL {
+            assert_eq!(strategy.first_blocking(grid.clone(), &repeated), None, "{strategy}");
+            assert_eq!(strategy.first_blocking(walled.clone(), &bytes), None, "{strategy}");
+        }
+    }
+
+    #[test]
+    fn header() {
+        let mut config = super::Config::default();
+        let bytes = super::parse_input(EXAMPLE, &mut config).unwrap();
+
+        assert_eq!((config.width, config.height, config.dropped), (7, 7, 12));
+        assert_eq!(bytes.len(), 25);
+
+        let grid = config.grid().unwrap();
+
+        assert_eq!(grid.start(), (0, 0));
+        assert_eq!(grid.goal(), (6, 6));
+    }
+
+    #[test]
+    fn options() {
+        let mut config = super::Config::default();
+        let bytes = super::parse_input(EXAMPLE, &mut config).unwrap();
+
+        // Walking the path backwards takes just as many steps.
+        config.set("start=6,6").unwrap();
+        config.set("goal=0,0").unwrap();
+
+        assert_eq!(super::part1(config.grid().unwrap(), &bytes, config.dropped), 22);
+
+        config.set("size=5x7").unwrap();
+        assert!(config.grid().is_err());
+
+        // The header sets a 7x7 grid, so the bytes only fit that or a larger one.
+        config.set("start=0,0").unwrap();
+        config.set("goal=4,4").unwrap();
+        config.set("size=5x5").unwrap();
+
+        let grid = config.grid().unwrap();
+        let err = bytes.check(&grid).unwrap_err();
+
+        assert_eq!(err.to_string(), "line 4: byte (5, 4) is outside of the 5x5 grid");
+
+        config.set("size=7x7").unwrap();
+        assert!(bytes.check(&config.grid().unwrap()).is_ok());
+
+        config.set("goal=7,0").unwrap();
+        assert!(config.grid().is_err());
 
-        assert_eq!(super::part2(grid, &bytes, 12), (6, 1));
+        assert!(config.set("size").is_err());
+        assert!(config.set("speed=1").is_err());
     }
 }
@@ -1,10 +1,12 @@
-use std::cmp::Reverse;
-use std::collections::hash_map::Entry;
-use std::collections::{BinaryHeap, HashMap};
+use std::fmt;
 
 use anyhow::{Context, Error, Result};
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 
+use self::trie::Trie;
+
+mod trie;
+
 const INPUT: &str = include_str!("./input");
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -16,6 +18,26 @@ enum Color {
     Green,
 }
 
+impl Color {
+    const COUNT: usize = 5;
+
+    const fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Color::White => "w",
+            Color::Blue => "u",
+            Color::Black => "b",
+            Color::Red => "r",
+            Color::Green => "g",
+        })
+    }
+}
+
 impl TryFrom<char> for Color {
     type Error = Error;
 
@@ -34,12 +56,9 @@ impl TryFrom<char> for Color {
 #[derive(Clone, Debug)]
 struct Pattern(Vec<Color>);
 
-impl Pattern {
-    fn is_prefix_to(&self, colors: &[Color]) -> bool {
-        let prefix = &self.0[..];
-        let n = prefix.len();
-
-        n <= colors.len() && prefix == &colors[..n]
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|color| color.fmt(f))
     }
 }
 
@@ -47,57 +66,140 @@ impl Pattern {
 struct Design(Vec<Color>);
 
 impl Design {
-    fn solutions(&self, patterns: &[Pattern]) -> u64 {
-        let mut queue = BinaryHeap::new();
-        let mut counts = HashMap::new();
+    /// Counts, for every offset into the design, the ways to arrange the rest of it.
+    fn suffix_counts(&self, trie: &Trie) -> Vec<u64> {
+        let mut counts = vec![0; self.0.len() + 1];
+        counts[self.0.len()] = 1;
+
+        for offset in (0..self.0.len()).rev() {
+            counts[offset] =
+                trie.prefixes(&self.0[offset..]).map(|(_, len)| counts[offset + len]).sum();
+        }
+
+        counts
+    }
+
+    fn solutions(&self, trie: &Trie) -> u64 {
+        self.suffix_counts(trie)[0]
+    }
+
+    /// Lazily lists every arrangement of patterns that makes up the design, as pattern indices.
+    fn arrangements<'a>(&'a self, trie: &'a Trie) -> Arrangements<'a> {
+        let counts = self.suffix_counts(trie);
+        let mut arrangements =
+            Arrangements { design: self, trie, counts, stack: Vec::new(), path: Vec::new() };
+
+        if arrangements.counts[0] > 0 {
+            arrangements.push(0);
+        }
+
+        arrangements
+    }
+}
+
+/// A depth-first walk over the arrangements of a design, which only steps into offsets that the
+/// rest of the design can still be arranged from.
+struct Arrangements<'a> {
+    design: &'a Design,
+    trie: &'a Trie,
+    counts: Vec<u64>,
+    /// The offsets along the current path, each with the patterns still left to try there.
+    stack: Vec<(usize, Vec<(usize, usize)>)>,
+    path: Vec<usize>,
+}
+
+impl Arrangements<'_> {
+    fn push(&mut self, offset: usize) {
+        let mut options = self
+            .trie
+            .prefixes(&self.design.0[offset..])
+            .filter(|&(_, len)| self.counts[offset + len] > 0)
+            .collect::<Vec<_>>();
+
+        // Try the shortest patterns first.
+        options.reverse();
+
+        self.stack.push((offset, options));
+    }
+}
 
-        counts.insert(0, 1);
-        queue.push(Reverse(0));
+impl Iterator for Arrangements<'_> {
+    type Item = Vec<usize>;
 
-        while let Some(Reverse(offset)) = queue.pop() {
-            let remaining = &self.0[offset..];
-            let count = counts[&offset];
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (offset, options) = self.stack.last_mut()?;
+            let offset = *offset;
 
-            if remaining.is_empty() {
-                return count;
+            if offset == self.design.0.len() {
+                let arrangement = self.path.clone();
+
+                self.stack.pop();
+                self.path.pop();
+
+                return Some(arrangement);
             }
 
-            for next_offset in
-                patterns.iter().filter(|p| p.is_prefix_to(remaining)).map(|p| offset + p.0.len())
-            {
-                match counts.entry(next_offset) {
-                    Entry::Occupied(entry) => {
-                        *entry.into_mut() += count;
-                        continue;
-                    }
-                    Entry::Vacant(entry) => {
-                        entry.insert(count);
-                    }
+            match options.pop() {
+                Some((pattern, len)) => {
+                    self.path.push(pattern);
+                    self.push(offset + len);
+                }
+                None => {
+                    self.stack.pop();
+                    self.path.pop();
                 }
-
-                queue.push(Reverse(next_offset));
             }
         }
-
-        0
     }
 }
 
 fn main() -> Result<()> {
     let (patterns, designs) = parse_input(INPUT).context("failed to parse input")?;
 
-    println!("part 1: {}", part1(&patterns, &designs));
-    println!("part 2: {}", part2(&patterns, &designs));
+    let mut args = std::env::args().skip(1);
+
+    match args.next().as_deref() {
+        None => {
+            println!("part 1: {}", part1(&patterns, &designs));
+            println!("part 2: {}", part2(&patterns, &designs));
+        }
+        Some("arrangements") => {
+            let limit = match args.next() {
+                Some(limit) => limit.parse().context("invalid number of arrangements")?,
+                None => 5,
+            };
+
+            let trie = Trie::new(&patterns);
+
+            for design in &designs {
+                let name = Pattern(design.0.clone());
+                println!("{name}: {} arrangements", design.solutions(&trie));
+
+                for arrangement in design.arrangements(&trie).take(limit) {
+                    let arrangement =
+                        arrangement.iter().map(|&i| patterns[i].to_string()).collect::<Vec<_>>();
+
+                    println!("    {}", arrangement.join(" "));
+                }
+            }
+        }
+        Some(command) => anyhow::bail!("unknown command: '{command}'"),
+    }
 
     Ok(())
 }
 
 fn part1(patterns: &[Pattern], designs: &[Design]) -> usize {
-    designs.par_iter().filter(|design| design.solutions(patterns) > 0).count()
+    let trie = Trie::new(patterns);
+
+    designs.par_iter().filter(|design| design.solutions(&trie) > 0).count()
 }
 
 fn part2(patterns: &[Pattern], designs: &[Design]) -> u64 {
-    designs.par_iter().map(|design| design.solutions(patterns)).sum()
+    let trie = Trie::new(patterns);
+
+    designs.par_iter().map(|design| design.solutions(&trie)).sum()
 }
 
 fn parse_input(input: &str) -> Result<(Vec<Pattern>, Vec<Design>)> {
@@ -134,4 +236,28 @@ mod example {
 
         assert_eq!(super::part2(&patterns, &designs), 16);
     }
+
+    #[test]
+    fn arrangements() {
+        let (patterns, designs) = super::parse_input(EXAMPLE).unwrap();
+        let trie = super::Trie::new(&patterns);
+
+        let spell = |arrangement: Vec<usize>| {
+            arrangement.iter().map(|&i| patterns[i].to_string()).collect::<Vec<_>>().join(" ")
+        };
+
+        let gbbr = designs[2].arrangements(&trie).map(spell).collect::<Vec<_>>();
+        assert_eq!(gbbr, ["g b b r", "g b br", "gb b r", "gb br"]);
+
+        for design in &designs {
+            let arrangements = design.arrangements(&trie).collect::<Vec<_>>();
+
+            assert_eq!(arrangements.len() as u64, design.solutions(&trie));
+
+            for arrangement in arrangements {
+                let colors = arrangement.iter().flat_map(|&i| &patterns[i].0).copied();
+                assert!(colors.eq(design.0.iter().copied()));
+            }
+        }
+    }
 }
@@ -0,0 +1,56 @@
+use crate::{Color, Pattern};
+
+#[derive(Clone, Debug, Default)]
+struct Node {
+    children: [Option<u32>; Color::COUNT],
+    /// The patterns ending at this node, which only has more than one for duplicate patterns.
+    patterns: Vec<usize>,
+}
+
+/// The towel patterns, merged into a tree where each path from the root spells out a prefix.
+#[derive(Clone, Debug)]
+pub struct Trie {
+    nodes: Vec<Node>,
+}
+
+impl Trie {
+    pub fn new(patterns: &[Pattern]) -> Trie {
+        let mut nodes = vec![Node::default()];
+
+        for (i, pattern) in patterns.iter().enumerate() {
+            let mut node = 0;
+
+            for &color in &pattern.0 {
+                node = match nodes[node].children[color.index()] {
+                    Some(child) => child as usize,
+                    None => {
+                        nodes.push(Node::default());
+                        nodes[node].children[color.index()] = Some((nodes.len() - 1) as u32);
+                        nodes.len() - 1
+                    }
+                };
+            }
+
+            nodes[node].patterns.push(i);
+        }
+
+        Trie { nodes }
+    }
+
+    /// Iterates over the patterns that `colors` starts with, as the pattern index and its length.
+    pub fn prefixes<'a>(
+        &'a self,
+        colors: &'a [Color],
+    ) -> impl Iterator<Item = (usize, usize)> + 'a {
+        let mut node = 0;
+
+        colors
+            .iter()
+            .map_while(move |&color| {
+                node = self.nodes[node].children[color.index()]? as usize;
+                Some(node)
+            })
+            .zip(1..)
+            .flat_map(move |(node, len)| self.nodes[node].patterns.iter().map(move |&i| (i, len)))
+    }
+}
@@ -1,13 +1,12 @@
-use std::cmp::Ordering;
-use std::collections::hash_map::Entry;
-use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::collections::HashMap;
 
 use anyhow::{Context, Result};
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
 
-use self::score::MinScored;
+use self::report::Report;
+use self::track::Track;
 
-mod score;
+mod report;
+mod track;
 
 const INPUT: &str = include_str!("./input");
 
@@ -63,132 +62,71 @@ impl Map {
         self.tiles.get(pos.y).and_then(|row| row.get(pos.x)).copied()
     }
 
-    fn manhattan_neighbors(&self, pos: Vec2, r: usize) -> impl Iterator<Item = Vec2> + '_ {
-        pos.manhattan_neighbors(r).filter(|pos| self.get(*pos).is_some())
-    }
-
     fn edges(&self, pos: Vec2) -> impl Iterator<Item = Vec2> + '_ {
         pos.manhattan_neighbors(1)
             .filter_map(|pos| self.get(pos).map(|tile| (pos, tile)))
             .filter(|(_, tile)| matches!(tile, Tile::Floor))
             .map(|(pos, _)| pos)
     }
+}
 
-    fn dijkstra(
-        &self,
-        start: Vec2,
-        goal: Vec2,
-        limit: Option<usize>,
-    ) -> (HashMap<Vec2, usize>, HashMap<Vec2, Vec<Vec2>>) {
-        let mut queue = BinaryHeap::new();
-        let mut scores = HashMap::new();
-        let mut parents = HashMap::new();
-        let mut seen = HashSet::new();
-
-        scores.insert(start, 0);
-        queue.push(MinScored(0, start));
-
-        while let Some(MinScored(node_score, node)) = queue.pop() {
-            if !seen.insert(node) {
-                continue;
-            }
+fn main() -> Result<()> {
+    let input = parse_input(INPUT).context("failed to parse input")?;
 
-            if node == goal || limit == Some(node_score) {
-                break;
-            }
+    let mut args = std::env::args().skip(1);
 
-            for next in self.edges(node) {
-                if seen.contains(&next) {
-                    continue;
+    match args.next().as_deref() {
+        None => {
+            println!("part 1: {}", part1(&input));
+            println!("part 2: {}", part2(&input));
+        }
+        Some("report") => {
+            let mut cheat_len = 20;
+            let mut threshold = 100;
+            let mut top = 10;
+            let mut json = false;
+
+            for arg in args {
+                let (key, value) = arg.split_once('=').unwrap_or((&arg, ""));
+                let parse = || value.parse::<usize>().with_context(|| format!("invalid '{arg}'"));
+
+                match key {
+                    "len" => cheat_len = parse()?,
+                    "min" => threshold = parse()?,
+                    "top" => top = parse()?,
+                    "json" => json = true,
+                    _ => anyhow::bail!("unknown option: '{arg}'"),
                 }
+            }
 
-                let next_score = node_score + 1;
-
-                match scores.entry(next) {
-                    Entry::Vacant(entry) => {
-                        entry.insert(next_score);
-                        parents.insert(next, vec![node]);
-                    }
-                    Entry::Occupied(entry) => {
-                        let score = entry.into_mut();
-
-                        match next_score.cmp(score) {
-                            // Ignore nodes that have been reached though a shorter path.
-                            Ordering::Greater => continue,
-                            Ordering::Equal => {
-                                parents.entry(next).or_default().push(node);
-                            }
-                            Ordering::Less => {
-                                parents.insert(next, vec![node]);
-                                *score = next_score;
-                            }
-                        }
-                    }
-                }
+            let report = Report::new(cheats(&input, cheat_len), cheat_len, threshold, top);
 
-                queue.push(MinScored(next_score, next));
+            let mut out = String::new();
+            if json {
+                report.write_json(&mut out)?;
+            } else {
+                report.write_text(&mut out)?;
             }
+            print!("{out}");
         }
-
-        (scores, parents)
+        Some(command) => anyhow::bail!("unknown command: '{command}'"),
     }
-}
-
-fn main() -> Result<()> {
-    let input = parse_input(INPUT).context("failed to parse input")?;
-
-    println!("part 1: {}", part1(&input));
-    println!("part 2: {}", part2(&input));
 
     Ok(())
 }
 
 fn part1(input: &Input) -> usize {
-    cheats(input, 2).into_values().filter(|save| *save >= 100).count()
+    Track::new(input).map_or(0, |track| track.count(2, 100))
 }
 
 fn part2(input: &Input) -> usize {
-    cheats(input, 20).into_values().filter(|save| *save >= 100).count()
+    Track::new(input).map_or(0, |track| track.count(20, 100))
 }
 
 fn cheats(input: &Input, cheat_len: usize) -> HashMap<(Vec2, Vec2), usize> {
-    let Input { ref map, start, goal } = *input;
-
-    let (scores, parents) = map.dijkstra(start, goal, None);
-
-    let cheat_starts = {
-        let mut nodes = HashSet::from([goal]);
-        let mut stack = parents[&goal].clone();
-
-        while let Some(node) = stack.pop() {
-            if !nodes.insert(node) {
-                continue;
-            }
-
-            if let Some(parents) = parents.get(&node) {
-                stack.extend(parents);
-            }
-        }
-
-        nodes
-    };
+    let Some(track) = Track::new(input) else { return HashMap::new() };
 
-    cheat_starts
-        .into_par_iter()
-        .flat_map_iter(|cheat_start| {
-            map.manhattan_neighbors(cheat_start, cheat_len)
-                .map(move |cheat_end| (cheat_start, cheat_end))
-        })
-        .filter_map(|(cheat_start, cheat_end)| {
-            let start_score = scores[&cheat_start];
-            let end_score = scores.get(&cheat_end).copied()?;
-
-            let distance = cheat_start.manhattan_distance(cheat_end);
-            let save = end_score.checked_sub(start_score)?.checked_sub(distance)?;
-
-            Some(((cheat_start, cheat_end), save))
-        })
-        .collect()
+    track.cheats(cheat_len).into_iter().map(|(start, end, save)| ((start, end), save)).collect()
 }
 
 struct Input {
@@ -308,4 +246,43 @@ mod example {
         expect(74, 4);
         expect(76, 3);
     }
+
+    #[test]
+    fn report() {
+        let input = super::parse_input(EXAMPLE).unwrap();
+        let report = super::Report::new(super::cheats(&input, 2), 2, 10, 3);
+
+        assert_eq!(report.count, 10);
+        assert_eq!(
+            report.histogram.iter().map(|(&k, &v)| (k, v)).collect::<Vec<_>>(),
+            [(10, 2), (12, 3), (20, 1), (36, 1), (38, 1), (40, 1), (64, 1)]
+        );
+        assert_eq!(report.top.iter().map(|cheat| cheat.save).collect::<Vec<_>>(), [64, 40, 38]);
+
+        let mut json = String::new();
+        report.write_json(&mut json).unwrap();
+
+        assert!(json.contains("\"count\": 10,"));
+        assert!(json.contains("{ \"save\": 12, \"cheats\": 3 }"));
+        assert!(json.contains("{ \"save\": 64, \"start\": [7, 7], \"end\": [5, 7] }"));
+    }
+
+    #[test]
+    fn branching() {
+        // The shortest path goes around the middle wall, with a dead end off to the right.
+        let input = super::parse_input(
+            "#######\n\
+             #S#..E#\n\
+             #.#.###\n\
+             #.....#\n\
+             #######",
+        )
+        .unwrap();
+
+        let cheats = super::cheats(&input, 2);
+
+        assert_eq!(cheats.len(), 2);
+        assert_eq!(cheats[&(super::Vec2 { x: 1, y: 1 }, super::Vec2 { x: 3, y: 1 })], 4);
+        assert_eq!(cheats[&(super::Vec2 { x: 1, y: 2 }, super::Vec2 { x: 3, y: 2 })], 2);
+    }
 }
@@ -0,0 +1,111 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{self, Write};
+
+use crate::Vec2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cheat {
+    pub start: Vec2,
+    pub end: Vec2,
+    pub save: usize,
+}
+
+/// A summary of the cheats of at most `cheat_len` picoseconds that save at least `threshold`.
+#[derive(Clone, Debug)]
+pub struct Report {
+    pub cheat_len: usize,
+    pub threshold: usize,
+    pub count: usize,
+    /// The number of cheats by the time they save.
+    pub histogram: BTreeMap<usize, usize>,
+    /// The cheats that save the most time, ties broken by their coordinates.
+    pub top: Vec<Cheat>,
+}
+
+impl Report {
+    pub fn new(
+        cheats: HashMap<(Vec2, Vec2), usize>,
+        cheat_len: usize,
+        threshold: usize,
+        top: usize,
+    ) -> Report {
+        let mut cheats = cheats
+            .into_iter()
+            .filter(|&(_, save)| save >= threshold)
+            .map(|((start, end), save)| Cheat { start, end, save })
+            .collect::<Vec<_>>();
+
+        let mut histogram = BTreeMap::new();
+
+        for cheat in &cheats {
+            *histogram.entry(cheat.save).or_default() += 1;
+        }
+
+        cheats
+            .sort_unstable_by_key(|cheat| (std::cmp::Reverse(cheat.save), cheat.start, cheat.end));
+
+        let count = cheats.len();
+        cheats.truncate(top);
+
+        Report { cheat_len, threshold, count, histogram, top: cheats }
+    }
+
+    pub fn write_text(&self, out: &mut impl Write) -> fmt::Result {
+        let Report { cheat_len, threshold, count, .. } = *self;
+
+        writeln!(out, "{count} cheats of up to {cheat_len} ps save at least {threshold} ps")?;
+
+        if self.histogram.is_empty() {
+            return Ok(());
+        }
+
+        writeln!(out)?;
+        writeln!(out, "{:>8} {:>8}", "save", "cheats")?;
+
+        for (save, n) in &self.histogram {
+            writeln!(out, "{save:>8} {n:>8}")?;
+        }
+
+        writeln!(out)?;
+        writeln!(out, "{:>8}  {:<12} end", "save", "start")?;
+
+        for Cheat { start, end, save } in &self.top {
+            let start = format!("{},{}", start.x, start.y);
+            let end = format!("{},{}", end.x, end.y);
+
+            writeln!(out, "{save:>8}  {start:<12} {end}")?;
+        }
+
+        Ok(())
+    }
+
+    pub fn write_json(&self, out: &mut impl Write) -> fmt::Result {
+        let Report { cheat_len, threshold, count, .. } = *self;
+
+        writeln!(out, "{{")?;
+        writeln!(out, "  \"cheat_len\": {cheat_len},")?;
+        writeln!(out, "  \"threshold\": {threshold},")?;
+        writeln!(out, "  \"count\": {count},")?;
+
+        // JSON object keys have to be strings, so the histogram is a list of pairs instead.
+        write!(out, "  \"histogram\": [")?;
+        for (i, (save, n)) in self.histogram.iter().enumerate() {
+            let sep = if i == 0 { "" } else { "," };
+            write!(out, "{sep}\n    {{ \"save\": {save}, \"cheats\": {n} }}")?;
+        }
+        writeln!(out, "{}],", if self.histogram.is_empty() { "" } else { "\n  " })?;
+
+        write!(out, "  \"top\": [")?;
+        for (i, Cheat { start, end, save }) in self.top.iter().enumerate() {
+            let sep = if i == 0 { "" } else { "," };
+            write!(
+                out,
+                "{sep}\n    {{ \"save\": {save}, \"start\": [{}, {}], \"end\": [{}, {}] }}",
+                start.x, start.y, end.x, end.y,
+            )?;
+        }
+        writeln!(out, "{}]", if self.top.is_empty() { "" } else { "\n  " })?;
+
+        writeln!(out, "}}")
+    }
+}
@@ -0,0 +1,134 @@
+use std::collections::VecDeque;
+
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::{Input, Map, Tile, Vec2};
+
+const UNREACHABLE: u32 = u32::MAX;
+
+/// The distances from the start and to the goal for every tile of the map, indexed by position.
+///
+/// A cheat from `a` to `b` then takes `from_start[a] + |a - b| + to_goal[b]` in total, which
+/// holds for any maze rather than only for a single track.
+#[derive(Clone, Debug)]
+pub struct Track {
+    width: usize,
+    height: usize,
+    from_start: Vec<u32>,
+    to_goal: Vec<u32>,
+    best: u32,
+}
+
+impl Track {
+    /// Returns `None` if the goal cannot be reached from the start.
+    pub fn new(input: &Input) -> Option<Track> {
+        let Input { ref map, start, goal } = *input;
+
+        let width = map.tiles.iter().map(Vec::len).max().unwrap_or(0);
+        let height = map.tiles.len();
+
+        let from_start = distances(map, width, start);
+        let to_goal = distances(map, width, goal);
+
+        let best = from_start[goal.x + goal.y * width];
+
+        (best != UNREACHABLE).then_some(Track { width, height, from_start, to_goal, best })
+    }
+
+    /// Calls `f` with every cheat starting on row `y` that saves time, along with the time saved.
+    fn for_each_cheat_in_row(
+        &self,
+        y: usize,
+        cheat_len: usize,
+        mut f: impl FnMut(Vec2, Vec2, u32),
+    ) {
+        let r = cheat_len as isize;
+
+        for x in 0..self.width {
+            let start = Vec2 { x, y };
+
+            let before = self.from_start[x + y * self.width];
+            if before == UNREACHABLE {
+                continue;
+            }
+
+            for dy in -r..=r {
+                let Some(end_y) = y.checked_add_signed(dy).filter(|&y| y < self.height) else {
+                    continue;
+                };
+
+                let r = r - dy.abs();
+
+                for dx in -r..=r {
+                    let Some(end_x) = x.checked_add_signed(dx).filter(|&x| x < self.width) else {
+                        continue;
+                    };
+
+                    let after = self.to_goal[end_x + end_y * self.width];
+                    if after == UNREACHABLE {
+                        continue;
+                    }
+
+                    let end = Vec2 { x: end_x, y: end_y };
+                    let total = before + start.manhattan_distance(end) as u32 + after;
+
+                    if total < self.best {
+                        f(start, end, self.best - total);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Counts the cheats of at most `cheat_len` picoseconds that save at least `threshold`.
+    pub fn count(&self, cheat_len: usize, threshold: usize) -> usize {
+        (0..self.height)
+            .into_par_iter()
+            .map(|y| {
+                let mut count = 0;
+                self.for_each_cheat_in_row(y, cheat_len, |_, _, save| {
+                    count += (save as usize >= threshold) as usize;
+                });
+                count
+            })
+            .sum()
+    }
+
+    /// Collects every cheat of at most `cheat_len` picoseconds that saves time.
+    pub fn cheats(&self, cheat_len: usize) -> Vec<(Vec2, Vec2, usize)> {
+        let mut cheats = Vec::new();
+
+        for y in 0..self.height {
+            self.for_each_cheat_in_row(y, cheat_len, |start, end, save| {
+                cheats.push((start, end, save as usize));
+            });
+        }
+
+        cheats
+    }
+}
+
+/// Finds the distance from `from` to every tile with a breadth-first search.
+fn distances(map: &Map, width: usize, from: Vec2) -> Vec<u32> {
+    let mut distances = vec![UNREACHABLE; width * map.tiles.len()];
+    let mut queue = VecDeque::new();
+
+    if map.get(from) == Some(Tile::Floor) {
+        distances[from.x + from.y * width] = 0;
+        queue.push_back(from);
+    }
+
+    while let Some(node) = queue.pop_front() {
+        let distance = distances[node.x + node.y * width] + 1;
+
+        for next in map.edges(node) {
+            let index = next.x + next.y * width;
+            if distances[index] == UNREACHABLE {
+                distances[index] = distance;
+                queue.push_back(next);
+            }
+        }
+    }
+
+    distances
+}
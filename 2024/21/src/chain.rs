@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+
+use crate::layout::Layout;
+use crate::{build_arrow_cost_levels, shortest_path, steps, ArrowKey, Key};
+
+/// Builds one of the cheapest sequences of presses on the human's keypad that makes the robot at
+/// `layout` type `keys`, through `robots` robots at directional keypads.
+pub fn presses<K: Key>(
+    layout: &Layout<K>,
+    arrows: &Layout<ArrowKey>,
+    keys: &[K],
+    robots: u32,
+) -> Option<Vec<ArrowKey>> {
+    let levels = build_arrow_cost_levels(arrows, robots);
+
+    let presses = paths(layout, &levels[robots as usize], keys)?;
+
+    expand(arrows, &levels, presses)
+}
+
+/// Joins the cheapest presses for each step of typing `keys`, or `None` if one can't be reached.
+fn paths<K: Key>(
+    layout: &Layout<K>,
+    costs: &HashMap<[ArrowKey; 2], u64>,
+    keys: &[K],
+) -> Option<Vec<ArrowKey>> {
+    let mut presses = Vec::new();
+
+    for [start, goal] in steps(layout, keys) {
+        presses.extend(shortest_path(layout, costs, start, goal)?.1);
+    }
+
+    Some(presses)
+}
+
+/// Turns presses on the directional keypad at the bottom of `levels` into the presses on the
+/// keypad above it, all the way up to the human.
+fn expand(
+    arrows: &Layout<ArrowKey>,
+    levels: &[HashMap<[ArrowKey; 2], u64>],
+    mut presses: Vec<ArrowKey>,
+) -> Option<Vec<ArrowKey>> {
+    for costs in levels.iter().rev().skip(1) {
+        presses = paths(arrows, costs, &presses)?;
+    }
+
+    Some(presses)
+}
+
+/// Replays the presses on `layout`, returning the keys it activates.
+fn replay<K: Key>(layout: &Layout<K>, presses: &[ArrowKey]) -> Result<Vec<K>> {
+    let mut pointer = layout.activate();
+    let mut keys = Vec::new();
+
+    for (i, &press) in presses.iter().enumerate() {
+        if press == ArrowKey::Activate {
+            keys.push(pointer);
+        } else {
+            pointer = layout.moved(pointer, press).with_context(|| {
+                format!("press {i} ({press}) moves off the keypad at {pointer:?}")
+            })?;
+        }
+    }
+
+    Ok(keys)
+}
+
+/// Replays the human's presses down the chain of `robots` directional keypads, returning what the
+/// last robot types on `layout`.
+pub fn simulate<K: Key>(
+    layout: &Layout<K>,
+    arrows: &Layout<ArrowKey>,
+    presses: &[ArrowKey],
+    robots: u32,
+) -> Result<Vec<K>> {
+    let mut presses = presses.to_vec();
+
+    for robot in 0..robots {
+        presses =
+            replay(arrows, &presses).with_context(|| format!("robot {} panicked", robot + 1))?;
+    }
+
+    replay(layout, &presses).context("last robot panicked")
+}
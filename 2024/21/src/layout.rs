@@ -0,0 +1,122 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{Context, Result};
+
+use crate::{ArrowKey, Key, Neighbor};
+
+/// A keypad laid out on a grid, parsed from an ASCII diagram with its rows separated by `/` and a
+/// space for every gap, such as `789/456/123/ 0A`.
+#[derive(Clone, Debug)]
+pub struct Layout<K> {
+    keys: Vec<K>,
+    activate: K,
+    neighbors: HashMap<K, Vec<Neighbor<K>>>,
+}
+
+impl<K: Key> Layout<K> {
+    /// Parses the diagram, turning each character other than a space into a key with `key`.
+    ///
+    /// The keypad has to contain an `A` key, which is where the pointer starts, and every key has
+    /// to be reachable from it.
+    pub fn parse(diagram: &str, key: impl Fn(char) -> Result<K>) -> Result<Layout<K>> {
+        let mut grid = HashMap::new();
+        let mut keys = Vec::new();
+        let mut names = HashMap::new();
+        let mut activate = None;
+
+        for (y, row) in diagram.split('/').enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                if c == ' ' {
+                    continue;
+                }
+
+                let k = key(c)?;
+
+                anyhow::ensure!(!keys.contains(&k), "key '{c}' appears more than once");
+
+                if c == 'A' {
+                    activate = Some(k);
+                }
+
+                keys.push(k);
+                names.insert(k, c);
+                grid.insert((x, y), k);
+            }
+        }
+
+        let activate = activate.context("keypad has no 'A' key")?;
+
+        let neighbors = grid
+            .iter()
+            .map(|(&(x, y), &k)| {
+                let neighbors = [
+                    (Some(x), y.checked_sub(1), ArrowKey::Up),
+                    (x.checked_add(1), Some(y), ArrowKey::Right),
+                    (Some(x), y.checked_add(1), ArrowKey::Down),
+                    (x.checked_sub(1), Some(y), ArrowKey::Left),
+                ]
+                .into_iter()
+                .filter_map(|(x, y, press)| {
+                    let key = *grid.get(&(x?, y?))?;
+                    Some(Neighbor { key, press })
+                })
+                .collect();
+
+                (k, neighbors)
+            })
+            .collect();
+
+        let layout = Layout { keys, activate, neighbors };
+
+        let mut seen = HashSet::from([activate]);
+        let mut stack = vec![activate];
+
+        while let Some(k) = stack.pop() {
+            stack.extend(layout.neighbors(k).iter().map(|n| n.key).filter(|&k| seen.insert(k)));
+        }
+
+        if let Some(k) = layout.keys.iter().find(|k| !seen.contains(k)) {
+            anyhow::bail!("key '{}' cannot be reached from the 'A' key", names[k]);
+        }
+
+        Ok(layout)
+    }
+
+    pub fn keys(&self) -> &[K] {
+        &self.keys
+    }
+
+    /// The key the pointer rests on before the first press.
+    pub fn activate(&self) -> K {
+        self.activate
+    }
+
+    pub fn neighbors(&self, key: K) -> &[Neighbor<K>] {
+        self.neighbors.get(&key).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the key the pointer moves to when pressing `press`, or `None` if it would move off
+    /// the keypad.
+    pub fn moved(&self, key: K, press: ArrowKey) -> Option<K> {
+        self.neighbors(key)
+            .iter()
+            .find(|neighbor| neighbor.press == press)
+            .map(|neighbor| neighbor.key)
+    }
+}
+
+impl Layout<ArrowKey> {
+    /// Parses a directional keypad, which has to contain all four arrows besides `A`.
+    pub fn directional(diagram: &str) -> Result<Layout<ArrowKey>> {
+        let layout = Layout::parse(diagram, ArrowKey::try_from)?;
+
+        for arrow in ArrowKey::LIST {
+            anyhow::ensure!(
+                layout.keys.contains(&arrow),
+                "directional keypad has no '{arrow}' key"
+            );
+        }
+
+        Ok(layout)
+    }
+}
@@ -3,15 +3,24 @@
 use std::cmp::Reverse;
 use std::collections::hash_map::Entry;
 use std::collections::{BinaryHeap, HashMap, HashSet};
-use std::fmt::Debug;
+use std::fmt::{self, Debug};
+use std::hash::Hash;
 
 use anyhow::{Context, Result};
 
+use self::layout::Layout;
+
+mod chain;
+mod layout;
+
 const INPUT: &str = include_str!("./input");
 
-trait Keypad: Copy + Ord + Debug {
-    fn neighbors(&self) -> &[Neighbor<Self>];
-}
+const NUMERIC: &str = "789/456/123/ 0A";
+const DIRECTIONAL: &str = " ^A/<v>";
+
+trait Key: Copy + Ord + Hash + Debug {}
+
+impl<T: Copy + Ord + Hash + Debug> Key for T {}
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 struct Neighbor<T> {
@@ -52,68 +61,6 @@ impl NumKey {
     }
 }
 
-impl Keypad for NumKey {
-    fn neighbors(&self) -> &[Neighbor<Self>] {
-        use ArrowKey as A;
-        use NumKey as N;
-
-        match self {
-            N::Num0 => &[
-                Neighbor { key: N::Num2, press: A::Up },
-                Neighbor { key: N::Activate, press: A::Right },
-            ],
-            N::Num1 => &[
-                Neighbor { key: N::Num4, press: A::Up },
-                Neighbor { key: N::Num2, press: A::Right },
-            ],
-            N::Num2 => &[
-                Neighbor { key: N::Num5, press: A::Up },
-                Neighbor { key: N::Num3, press: A::Right },
-                Neighbor { key: N::Num0, press: A::Down },
-                Neighbor { key: N::Num1, press: A::Left },
-            ],
-            N::Num3 => &[
-                Neighbor { key: N::Num6, press: A::Up },
-                Neighbor { key: N::Activate, press: A::Down },
-                Neighbor { key: N::Num2, press: A::Left },
-            ],
-            N::Num4 => &[
-                Neighbor { key: N::Num7, press: A::Up },
-                Neighbor { key: N::Num5, press: A::Right },
-                Neighbor { key: N::Num1, press: A::Down },
-            ],
-            N::Num5 => &[
-                Neighbor { key: N::Num8, press: A::Up },
-                Neighbor { key: N::Num6, press: A::Right },
-                Neighbor { key: N::Num2, press: A::Down },
-                Neighbor { key: N::Num4, press: A::Left },
-            ],
-            N::Num6 => &[
-                Neighbor { key: N::Num9, press: A::Up },
-                Neighbor { key: N::Num3, press: A::Down },
-                Neighbor { key: N::Num5, press: A::Left },
-            ],
-            N::Num7 => &[
-                Neighbor { key: N::Num8, press: A::Right },
-                Neighbor { key: N::Num4, press: A::Down },
-            ],
-            N::Num8 => &[
-                Neighbor { key: N::Num9, press: A::Right },
-                Neighbor { key: N::Num5, press: A::Down },
-                Neighbor { key: N::Num7, press: A::Left },
-            ],
-            N::Num9 => &[
-                Neighbor { key: N::Num6, press: A::Down },
-                Neighbor { key: N::Num8, press: A::Left },
-            ],
-            N::Activate => &[
-                Neighbor { key: N::Num3, press: A::Up },
-                Neighbor { key: N::Num0, press: A::Left },
-            ],
-        }
-    }
-}
-
 impl TryFrom<char> for NumKey {
     type Error = anyhow::Error;
 
@@ -151,36 +98,49 @@ impl ArrowKey {
         [ArrowKey::Up, ArrowKey::Right, ArrowKey::Down, ArrowKey::Left, ArrowKey::Activate];
 }
 
-impl Keypad for ArrowKey {
-    fn neighbors(&self) -> &[Neighbor<Self>] {
-        use ArrowKey as A;
+impl fmt::Display for ArrowKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ArrowKey::Up => "^",
+            ArrowKey::Right => ">",
+            ArrowKey::Down => "v",
+            ArrowKey::Left => "<",
+            ArrowKey::Activate => "A",
+        })
+    }
+}
 
-        match self {
-            A::Up => &[
-                Neighbor { key: A::Activate, press: A::Right },
-                Neighbor { key: A::Down, press: A::Down },
-            ],
-            A::Right => &[
-                Neighbor { key: A::Activate, press: A::Up },
-                Neighbor { key: A::Down, press: A::Left },
-            ],
-            A::Down => &[
-                Neighbor { key: A::Up, press: A::Up },
-                Neighbor { key: A::Right, press: A::Right },
-                Neighbor { key: A::Left, press: A::Left },
-            ],
-            A::Left => &[Neighbor { key: A::Down, press: A::Right }],
-            A::Activate => &[
-                Neighbor { key: A::Right, press: A::Down },
-                Neighbor { key: A::Up, press: A::Left },
-            ],
-        }
+impl TryFrom<char> for ArrowKey {
+    type Error = anyhow::Error;
+
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        Ok(match c {
+            '^' => ArrowKey::Up,
+            '>' => ArrowKey::Right,
+            'v' => ArrowKey::Down,
+            '<' => ArrowKey::Left,
+            'A' => ArrowKey::Activate,
+            _ => anyhow::bail!("unknown arrow key: '{c}'"),
+        })
     }
 }
 
 #[derive(Clone, Debug)]
 struct NumSequence(Vec<NumKey>);
 
+impl fmt::Display for NumSequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for key in &self.0 {
+            match key.digit() {
+                Some(digit) => write!(f, "{digit}")?,
+                None => f.write_str("A")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
 impl NumSequence {
     fn to_numeric(&self) -> u64 {
         let mut n = 0;
@@ -193,18 +153,37 @@ impl NumSequence {
         n
     }
 
-    fn steps(&self) -> impl Iterator<Item = [NumKey; 2]> + '_ {
-        std::iter::once(NumKey::Activate)
-            .chain(self.0.iter().copied())
-            .map_windows(|&[start, goal]| [start, goal])
+    fn cost(&self, numeric: &Layout<NumKey>, costs: &HashMap<[ArrowKey; 2], u64>) -> Option<u64> {
+        sequence_cost(numeric, costs, &self.0)
     }
+}
 
-    fn cost(&self, costs: &HashMap<[ArrowKey; 2], u64>) -> u64 {
-        self.steps().map(|[start, goal]| path_cost(costs, start, goal)).sum()
-    }
+/// Pairs up each key with the one before it, starting from the activate key.
+fn steps<'a, K: Key>(layout: &Layout<K>, keys: &'a [K]) -> impl Iterator<Item = [K; 2]> + 'a {
+    std::iter::once(layout.activate())
+        .chain(keys.iter().copied())
+        .map_windows(|&[start, goal]| [start, goal])
+}
+
+/// Counts the presses needed to type `keys` on `layout`, with the arrow costs of the keypad chain
+/// operating it, or `None` if one of the keys can't be reached.
+fn sequence_cost<K: Key>(
+    layout: &Layout<K>,
+    costs: &HashMap<[ArrowKey; 2], u64>,
+    keys: &[K],
+) -> Option<u64> {
+    steps(layout, keys).map(|[start, goal]| path_cost(layout, costs, start, goal)).sum()
 }
 
-fn build_arrow_costs(level: u32) -> HashMap<[ArrowKey; 2], u64> {
+fn build_arrow_costs(arrows: &Layout<ArrowKey>, level: u32) -> HashMap<[ArrowKey; 2], u64> {
+    build_arrow_cost_levels(arrows, level).pop().expect("level 0 is always built")
+}
+
+/// Builds the arrow costs for every level from 0 up to `level`.
+fn build_arrow_cost_levels(
+    arrows: &Layout<ArrowKey>,
+    level: u32,
+) -> Vec<HashMap<[ArrowKey; 2], u64>> {
     let mut costs = HashMap::new();
 
     // Start with uniform cost for level 0.
@@ -214,10 +193,12 @@ fn build_arrow_costs(level: u32) -> HashMap<[ArrowKey; 2], u64> {
         }
     }
 
+    let mut levels = Vec::with_capacity(level as usize + 1);
+
     for _ in 0..level {
         let mut new_costs = HashMap::new();
 
-        for start in ArrowKey::LIST {
+        for &start in arrows.keys() {
             let mut seen = HashSet::new();
             let mut queue = BinaryHeap::new();
 
@@ -238,7 +219,7 @@ fn build_arrow_costs(level: u32) -> HashMap<[ArrowKey; 2], u64> {
 
                 seen.insert(node);
 
-                for &neighbor in node.neighbors() {
+                for &neighbor in arrows.neighbors(node) {
                     if seen.contains(&neighbor.key) {
                         continue;
                     }
@@ -252,63 +233,166 @@ fn build_arrow_costs(level: u32) -> HashMap<[ArrowKey; 2], u64> {
             }
         }
 
-        costs = new_costs;
+        levels.push(std::mem::replace(&mut costs, new_costs));
     }
 
-    costs
+    levels.push(costs);
+    levels
 }
 
-fn path_cost<T: Keypad>(costs: &HashMap<[ArrowKey; 2], u64>, start: T, goal: T) -> u64 {
+fn path_cost<K: Key>(
+    layout: &Layout<K>,
+    costs: &HashMap<[ArrowKey; 2], u64>,
+    start: K,
+    goal: K,
+) -> Option<u64> {
+    shortest_path(layout, costs, start, goal).map(|(cost, _)| cost)
+}
+
+/// Finds the cheapest arrow presses that move the pointer from `start` to `goal` and press it,
+/// along with their cost, or `None` if `goal` can't be reached.
+fn shortest_path<K: Key>(
+    layout: &Layout<K>,
+    costs: &HashMap<[ArrowKey; 2], u64>,
+    start: K,
+    goal: K,
+) -> Option<(u64, Vec<ArrowKey>)> {
     let mut queue = BinaryHeap::new();
+    let mut parents = HashMap::new();
 
-    queue.push((Reverse(0), start, ArrowKey::Activate));
+    // The previous press is `None` until the first press, with the pointer above still resting on
+    // its activate key.
+    queue.push((Reverse(0), start, None, None));
 
-    while let Some((Reverse(cost), node, prev)) = queue.pop() {
-        if node == goal {
-            if prev == ArrowKey::Activate {
-                return cost;
+    while let Some((Reverse(cost), node, prev, parent)) = queue.pop() {
+        if let Entry::Vacant(entry) = parents.entry((node, prev)) {
+            entry.insert(parent);
+        } else {
+            continue;
+        }
+
+        if node == goal && prev == Some(ArrowKey::Activate) {
+            let mut presses = Vec::new();
+            let mut state = (node, prev);
+
+            while let (_, Some(press)) = state {
+                presses.push(press);
+                state = parents[&state].expect("only the start state has no parent");
             }
 
-            queue.push((
-                Reverse(cost + costs[&[prev, ArrowKey::Activate]]),
-                node,
-                ArrowKey::Activate,
-            ));
+            presses.reverse();
+
+            return Some((cost, presses));
+        }
+
+        let last = prev.unwrap_or(ArrowKey::Activate);
+
+        if node == goal {
+            let next_cost = cost + costs[&[last, ArrowKey::Activate]];
+
+            queue.push((Reverse(next_cost), node, Some(ArrowKey::Activate), Some((node, prev))));
 
             continue;
         }
 
-        for neighbor in node.neighbors() {
+        for neighbor in layout.neighbors(node) {
             queue.push((
-                Reverse(cost + costs[&[prev, neighbor.press]]),
+                Reverse(cost + costs[&[last, neighbor.press]]),
                 neighbor.key,
-                neighbor.press,
+                Some(neighbor.press),
+                Some((node, prev)),
             ));
         }
     }
 
-    panic!("no path found from '{start:?}' to '{goal:?}'");
+    None
 }
 
 fn main() -> Result<()> {
     let input = parse_input(INPUT).context("failed to parse input")?;
 
-    println!("part 1: {}", part1(&input));
-    println!("part 2: {}", part2(&input));
+    let numeric = Layout::parse(NUMERIC, NumKey::try_from)?;
+    let arrows = Layout::directional(DIRECTIONAL)?;
+
+    let mut args = std::env::args().skip(1);
+
+    match args.next().as_deref() {
+        None => {
+            let part1 = part1(&input, &numeric, &arrows).context("no path found")?;
+            let part2 = part2(&input, &numeric, &arrows).context("no path found")?;
+
+            println!("part 1: {part1}");
+            println!("part 2: {part2}");
+        }
+        Some("presses") => {
+            let robots = match args.next() {
+                Some(robots) => robots.parse().context("invalid number of robots")?,
+                None => 2,
+            };
+
+            for seq in &input {
+                let presses = chain::presses(&numeric, &arrows, &seq.0, robots)
+                    .with_context(|| format!("no presses type {seq}"))?;
+                let typed = chain::simulate(&numeric, &arrows, &presses, robots)?;
+
+                anyhow::ensure!(typed == seq.0, "presses for {seq} type {typed:?} instead");
+
+                let presses = presses.iter().map(ArrowKey::to_string).collect::<String>();
+                println!("{seq}: {presses} ({} presses)", presses.len());
+            }
+        }
+        Some("layout") => {
+            let diagram = args.next().context("missing keypad diagram")?;
+            let robots = args.next().context("missing number of robots")?;
+            let robots = robots.parse().context("invalid number of robots")?;
+
+            let layout = Layout::parse(&diagram, Ok)?;
+            let costs = build_arrow_costs(&arrows, robots);
+
+            for code in args {
+                let keys = code.chars().collect::<Vec<_>>();
+
+                if let Some(key) = keys.iter().find(|key| !layout.keys().contains(key)) {
+                    anyhow::bail!("key '{key}' of code {code} is not on the keypad");
+                }
+
+                let cost = sequence_cost(&layout, &costs, &keys)
+                    .with_context(|| format!("no presses type {code}"))?;
+
+                println!("{code}: {cost} presses");
+            }
+        }
+        Some(command) => anyhow::bail!("unknown command: '{command}'"),
+    }
 
     Ok(())
 }
 
-fn part1(input: &[NumSequence]) -> u64 {
-    let costs = build_arrow_costs(2);
+fn part1(
+    input: &[NumSequence],
+    numeric: &Layout<NumKey>,
+    arrows: &Layout<ArrowKey>,
+) -> Option<u64> {
+    complexity(input, numeric, arrows, 2)
+}
 
-    input.iter().map(|seq| seq.to_numeric() * seq.cost(&costs)).sum()
+fn part2(
+    input: &[NumSequence],
+    numeric: &Layout<NumKey>,
+    arrows: &Layout<ArrowKey>,
+) -> Option<u64> {
+    complexity(input, numeric, arrows, 25)
 }
 
-fn part2(input: &[NumSequence]) -> u64 {
-    let costs = build_arrow_costs(25);
+fn complexity(
+    input: &[NumSequence],
+    numeric: &Layout<NumKey>,
+    arrows: &Layout<ArrowKey>,
+    robots: u32,
+) -> Option<u64> {
+    let costs = build_arrow_costs(arrows, robots);
 
-    input.iter().map(|seq| seq.to_numeric() * seq.cost(&costs)).sum()
+    input.iter().map(|seq| Some(seq.to_numeric() * seq.cost(numeric, &costs)?)).sum()
 }
 
 fn parse_input(input: &str) -> Result<Vec<NumSequence>> {
@@ -326,6 +410,118 @@ mod example {
     fn part1() {
         let input = super::parse_input(EXAMPLE).unwrap();
 
-        assert_eq!(super::part1(&input), 126384);
+        let numeric = super::Layout::parse(super::NUMERIC, super::NumKey::try_from).unwrap();
+        let arrows = super::Layout::directional(super::DIRECTIONAL).unwrap();
+
+        assert_eq!(super::part1(&input, &numeric, &arrows), Some(126384));
+    }
+
+    #[test]
+    fn presses() {
+        let input = super::parse_input(EXAMPLE).unwrap();
+
+        let numeric = super::Layout::parse(super::NUMERIC, super::NumKey::try_from).unwrap();
+        let arrows = super::Layout::directional(super::DIRECTIONAL).unwrap();
+
+        for robots in 0..=3 {
+            let costs = super::build_arrow_costs(&arrows, robots);
+
+            for seq in &input {
+                let presses = super::chain::presses(&numeric, &arrows, &seq.0, robots).unwrap();
+                let typed = super::chain::simulate(&numeric, &arrows, &presses, robots).unwrap();
+
+                assert_eq!(
+                    Some(presses.len() as u64),
+                    seq.cost(&numeric, &costs),
+                    "{seq} at {robots}"
+                );
+                assert_eq!(typed, seq.0);
+            }
+        }
+
+        let presses = super::chain::presses(&numeric, &arrows, &input[0].0, 2).unwrap();
+
+        assert_eq!(presses.len(), 68);
+    }
+
+    #[test]
+    fn simulate() {
+        use super::ArrowKey::*;
+
+        let numeric = super::Layout::parse(super::NUMERIC, super::NumKey::try_from).unwrap();
+        let arrows = super::Layout::directional(super::DIRECTIONAL).unwrap();
+
+        // Typing 029A directly on the numeric keypad.
+        let presses = [Left, Activate, Up, Activate, Up, Up, Right, Activate, Down, Down, Down];
+        let typed = super::chain::simulate(&numeric, &arrows, &presses, 0).unwrap();
+
+        assert_eq!(typed, super::parse_input("029").unwrap()[0].0);
+
+        // The gap left of 0 is off the keypad.
+        assert!(super::chain::simulate(&numeric, &arrows, &[Left, Left], 0).is_err());
+    }
+}
+
+#[cfg(test)]
+mod keypad {
+    use super::layout::Layout;
+    use super::{ArrowKey, Neighbor, NumKey};
+
+    #[test]
+    fn neighbors() {
+        let numeric = Layout::parse(super::NUMERIC, NumKey::try_from).unwrap();
+
+        assert_eq!(numeric.activate(), NumKey::Activate);
+        assert_eq!(
+            numeric.neighbors(NumKey::Num0),
+            [
+                Neighbor { key: NumKey::Num2, press: ArrowKey::Up },
+                Neighbor { key: NumKey::Activate, press: ArrowKey::Right },
+            ]
+        );
+        assert_eq!(numeric.moved(NumKey::Num1, ArrowKey::Down), None);
+
+        let arrows = Layout::directional(super::DIRECTIONAL).unwrap();
+
+        assert_eq!(
+            arrows.neighbors(ArrowKey::Left),
+            [Neighbor { key: ArrowKey::Down, press: ArrowKey::Right }]
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(Layout::parse("12/1A", Ok).is_err());
+        assert!(Layout::parse("123/456", Ok).is_err());
+        assert!(Layout::directional("<^>/ vA").is_ok());
+        assert!(Layout::directional(" ^A/ v>").is_err());
+
+        // Keys that can't be reached from the others.
+        assert!(Layout::parse("1 2/ A", Ok).is_err());
+        assert!(Layout::parse("1A/  /2 ", Ok).is_err());
+    }
+
+    #[test]
+    fn custom() {
+        // A phone keypad, operated through a directional keypad with the arrows in one row.
+        let phone = Layout::parse("123/456/789/ 0A", Ok).unwrap();
+        let arrows = Layout::directional("<^v>A").unwrap();
+
+        for robots in 0..=3 {
+            let costs = super::build_arrow_costs(&arrows, robots);
+
+            for code in ["029A", "980A", "179A", "456A", "379A"] {
+                let keys = code.chars().collect::<Vec<_>>();
+
+                let presses = super::chain::presses(&phone, &arrows, &keys, robots).unwrap();
+                let typed = super::chain::simulate(&phone, &arrows, &presses, robots).unwrap();
+
+                assert_eq!(Some(presses.len() as u64), super::sequence_cost(&phone, &costs, &keys));
+                assert_eq!(typed, keys);
+            }
+
+            // Keys that aren't on the keypad can't be typed.
+            assert_eq!(super::sequence_cost(&phone, &costs, &['*']), None);
+        }
     }
 }
@@ -0,0 +1,88 @@
+use std::fmt;
+
+use crate::{window_totals, History, Prng};
+
+/// Why a window of price changes is the best one to sell at.
+#[derive(Clone, Debug)]
+pub struct Explanation<const N: usize> {
+    pub window: [i8; N],
+    pub total: u32,
+    /// The price each buyer sells at, or `None` if the window never appears for them.
+    pub sales: Vec<(Prng, Option<u8>)>,
+    /// The next best windows, along with their totals.
+    pub alternatives: Vec<([i8; N], u32)>,
+}
+
+/// Finds the best window of `N` price changes, along with the `top` windows after it.
+pub fn explain<const N: usize>(input: &[Prng], steps: usize, top: usize) -> Option<Explanation<N>> {
+    let mut totals = window_totals::<N>(input, steps)
+        .into_iter()
+        .enumerate()
+        .filter(|&(_, total)| total > 0)
+        .collect::<Vec<_>>();
+
+    totals.sort_unstable_by_key(|&(index, total)| (std::cmp::Reverse(total), index));
+    totals.truncate(top + 1);
+
+    let mut totals = totals.into_iter().map(|(index, total)| (History::<N>::decode(index), total));
+
+    let (window, total) = totals.next()?;
+
+    let sales = input.iter().map(|&start| (start, sell_price(start, window, steps))).collect();
+
+    Some(Explanation { window, total, sales, alternatives: totals.collect() })
+}
+
+/// Returns the price the buyer sells at when `window` first appears within `steps` steps.
+fn sell_price<const N: usize>(start: Prng, window: [i8; N], steps: usize) -> Option<u8> {
+    let mut history = start.start_history::<N>();
+
+    for _ in history.len()..steps {
+        if history.history() == window {
+            return Some(history.price());
+        }
+
+        history.advance();
+    }
+
+    None
+}
+
+struct Window<'a>(&'a [i8]);
+
+impl fmt::Display for Window<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let window = self.0.iter().map(i8::to_string).collect::<Vec<_>>();
+
+        // Pad the whole window, so it lines up in a table.
+        f.pad(&window.join(","))
+    }
+}
+
+impl<const N: usize> fmt::Display for Explanation<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "best window: {} ({} bananas)", Window(&self.window), self.total)?;
+        writeln!(f)?;
+        writeln!(f, "{:>10}  price", "buyer")?;
+
+        for (buyer, price) in &self.sales {
+            match price {
+                Some(price) => writeln!(f, "{:>10}  {price}", buyer.state)?,
+                None => writeln!(f, "{:>10}  never", buyer.state)?,
+            }
+        }
+
+        if self.alternatives.is_empty() {
+            return Ok(());
+        }
+
+        writeln!(f)?;
+        writeln!(f, "{:>4}  {:<16} bananas", "rank", "window")?;
+
+        for (rank, (window, total)) in self.alternatives.iter().enumerate() {
+            writeln!(f, "{:>4}  {:<16} {total}", rank + 2, Window(window))?;
+        }
+
+        Ok(())
+    }
+}
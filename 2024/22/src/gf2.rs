@@ -0,0 +1,134 @@
+use std::collections::{BTreeMap, HashMap};
+
+pub const BITS: usize = 24;
+
+/// A linear map on 24-bit vectors over GF(2), stored as the images of the basis vectors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Matrix {
+    columns: [u32; BITS],
+}
+
+impl Matrix {
+    pub const IDENTITY: Matrix = {
+        let mut columns = [0; BITS];
+        let mut i = 0;
+
+        while i < BITS {
+            columns[i] = 1 << i;
+            i += 1;
+        }
+
+        Matrix { columns }
+    };
+
+    /// Builds the matrix of a linear map from the images of the basis vectors.
+    pub const fn from_columns(columns: [u32; BITS]) -> Matrix {
+        Matrix { columns }
+    }
+
+    pub const fn apply(&self, vector: u32) -> u32 {
+        let mut result = 0;
+        let mut i = 0;
+
+        while i < BITS {
+            if vector >> i & 1 == 1 {
+                result ^= self.columns[i];
+            }
+            i += 1;
+        }
+
+        result
+    }
+
+    /// Composes the maps, applying `other` first.
+    pub const fn mul(&self, other: &Matrix) -> Matrix {
+        let mut columns = [0; BITS];
+        let mut i = 0;
+
+        while i < BITS {
+            columns[i] = self.apply(other.columns[i]);
+            i += 1;
+        }
+
+        Matrix { columns }
+    }
+
+    pub const fn pow(&self, mut n: u64) -> Matrix {
+        let mut result = Matrix::IDENTITY;
+        let mut base = *self;
+
+        while n > 0 {
+            if n & 1 == 1 {
+                result = result.mul(&base);
+            }
+            base = base.mul(&base);
+            n >>= 1;
+        }
+
+        result
+    }
+
+    /// Finds the smallest `n > 0` for which applying the map `n` times brings `vector` back to
+    /// itself, or `None` if it never does.
+    ///
+    /// This is a baby-step giant-step search, taking about `2 * 2^(BITS / 2)` steps rather than
+    /// walking the whole cycle.
+    pub fn period(&self, vector: u32) -> Option<u64> {
+        const STEPS: u64 = 1 << (BITS / 2);
+
+        let mut baby = HashMap::with_capacity(STEPS as usize);
+        let mut current = vector;
+
+        for j in 0..STEPS {
+            if j > 0 && current == vector {
+                return Some(j);
+            }
+            baby.insert(current, j);
+            current = self.apply(current);
+        }
+
+        let giant = self.pow(STEPS);
+        let mut current = giant.apply(vector);
+
+        // Every cycle fits in the state space, so no period is longer than 2^BITS.
+        for i in 1..=STEPS {
+            if let Some(&j) = baby.get(&current) {
+                // With a map that is not invertible, `vector` may lead into a cycle without being
+                // part of it.
+                let n = i * STEPS - j;
+                return (self.pow(n).apply(vector) == vector).then_some(n);
+            }
+            current = giant.apply(current);
+        }
+
+        None
+    }
+
+    /// Splits the state space into the cycles of the map, counting the cycles of each length.
+    ///
+    /// States that are not on any cycle are left out.
+    pub fn cycles(&self) -> BTreeMap<u64, u64> {
+        let mut visited = vec![0_u64; (1 << BITS) / 64];
+        let mut cycles = BTreeMap::new();
+
+        for vector in 0..1_u32 << BITS {
+            let (word, bit) = (vector as usize / 64, vector % 64);
+
+            if visited[word] >> bit & 1 == 1 {
+                continue;
+            }
+
+            let Some(period) = self.period(vector) else { continue };
+
+            let mut current = vector;
+            for _ in 0..period {
+                visited[current as usize / 64] |= 1 << (current % 64);
+                current = self.apply(current);
+            }
+
+            *cycles.entry(period).or_default() += 1;
+        }
+
+        cycles
+    }
+}
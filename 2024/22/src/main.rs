@@ -1,8 +1,11 @@
-use std::collections::hash_map::Entry;
-use std::collections::HashMap;
-
 use anyhow::{Context, Result};
-use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use rayon::iter::ParallelIterator;
+use rayon::slice::ParallelSlice;
+
+use self::gf2::Matrix;
+
+mod explain;
+mod gf2;
 
 const INPUT: &str = include_str!("./input");
 
@@ -12,6 +15,19 @@ struct Prng {
 }
 
 impl Prng {
+    /// The matrix of a single step, which only ever shifts and xors the bits of the state.
+    const STEP: Matrix = {
+        let mut columns = [0; gf2::BITS];
+        let mut i = 0;
+
+        while i < gf2::BITS {
+            columns[i] = Prng::from_seed(1 << i).next().state;
+            i += 1;
+        }
+
+        Matrix::from_columns(columns)
+    };
+
     const fn from_seed(seed: u32) -> Prng {
         Prng { state: seed }
     }
@@ -34,6 +50,19 @@ impl Prng {
         self.state
     }
 
+    /// Advances the generator by `n` steps at once.
+    fn skip(&mut self, n: u64) -> u32 {
+        self.state = Prng::STEP.pow(n).apply(self.state);
+        self.state
+    }
+
+    /// Returns the number of steps after which the generator returns to its current state.
+    fn period(self) -> u64 {
+        Prng::STEP
+            .period(self.state)
+            .expect("every step can be undone, so every state is on a cycle")
+    }
+
     const fn next(self) -> Prng {
         let mut next = self;
         next.advance();
@@ -89,10 +118,30 @@ impl<const N: usize> History<N> {
         self.history
     }
 
+    /// The number of distinct change windows, with every change being in `-9..=9`.
+    const WINDOWS: usize = 19_usize.pow(N as u32);
+
     const fn len(&self) -> usize {
         N
     }
 
+    /// Encodes the change window as a base-19 number in `0..WINDOWS`.
+    fn index(&self) -> usize {
+        self.history().iter().fold(0, |index, &change| index * 19 + (change + 9) as usize)
+    }
+
+    /// Turns an index back into the change window it encodes.
+    fn decode(mut index: usize) -> [i8; N] {
+        let mut window = [0; N];
+
+        for change in window.iter_mut().rev() {
+            *change = (index % 19) as i8 - 9;
+            index /= 19;
+        }
+
+        window
+    }
+
     fn advance(&mut self) -> u8 {
         let prev = self.prev;
         let next = prev.next();
@@ -109,52 +158,114 @@ impl<const N: usize> History<N> {
 fn main() -> Result<()> {
     let input = parse_input(INPUT).context("failed to parse input")?;
 
-    println!("part 1: {}", part1(&input));
-    println!("part 2: {}", part2(&input));
+    let mut args = std::env::args().skip(1);
+
+    match args.next().as_deref() {
+        None => {
+            println!("part 1: {}", part1(&input));
+            println!("part 2: {}", part2(&input));
+        }
+        Some("skip") => {
+            let seed = args.next().context("missing seed")?;
+            let steps = args.next().context("missing number of steps")?;
+
+            let mut prng = Prng::from_seed(seed.parse().context("invalid seed")?);
+            let steps = steps.parse().context("invalid number of steps")?;
+
+            println!("{}", prng.skip(steps));
+        }
+        Some("period") => {
+            for prng in &input {
+                println!("{}: {}", prng.state, prng.period());
+            }
+        }
+        Some("explain") => {
+            let top = match args.next() {
+                Some(top) => top.parse().context("invalid number of alternatives")?,
+                None => 10,
+            };
+
+            let explanation =
+                explain::explain::<4>(&input, 2000, top).context("no price history")?;
+            print!("{explanation}");
+        }
+        Some("cycles") => {
+            for (period, count) in Prng::STEP.cycles() {
+                println!("{count} cycles of length {period}");
+            }
+        }
+        Some(command) => anyhow::bail!("unknown command: '{command}'"),
+    }
 
     Ok(())
 }
 
 fn part1(input: &[Prng]) -> u64 {
-    input
-        .par_iter()
-        .copied()
-        .map(|mut prng| {
-            for _ in 0..2000 {
-                prng.advance();
-            }
-            prng.state
-        })
-        .map(u64::from)
-        .sum()
+    let jump = Prng::STEP.pow(2000);
+
+    input.iter().map(|prng| u64::from(jump.apply(prng.state))).sum()
 }
 
 fn part2(input: &[Prng]) -> u64 {
+    let totals = window_totals::<4>(input, 2000);
+
+    totals.into_iter().max().map(u64::from).expect("no price history")
+}
+
+/// Sums up, for every window of `N` price changes, the price each buyer sells at when the window
+/// first appears within `steps` steps, indexed by `History::index`.
+fn window_totals<const N: usize>(input: &[Prng], steps: usize) -> Vec<u32> {
+    // Each buyer adds at most 9 to a window, so this many buyers always fit into a `u16` table.
+    const MAX_CHUNK: usize = u16::MAX as usize / 9;
+
+    let chunk = input.len().div_ceil(rayon::current_num_threads()).clamp(1, MAX_CHUNK);
+    let windows = History::<N>::WINDOWS;
+
     input
-        .par_iter()
-        .map(|start| {
-            let mut prices = HashMap::new();
-            let mut history = start.start_history::<4>();
-
-            for _ in history.len()..2000 {
-                // Only count the first appearance of a sequence.
-                if let Entry::Vacant(entry) = prices.entry(history.history()) {
-                    entry.insert(u64::from(history.price()));
-                }
+        .par_chunks(chunk)
+        .map(|buyers| {
+            let mut table = vec![0_u16; windows];
+            let mut seen = vec![0_u64; windows.div_ceil(64)];
 
-                history.advance();
-            }
+            for start in buyers {
+                seen.fill(0);
 
-            prices
-        })
-        .reduce_with(|mut a, b| {
-            for (history, price) in b {
-                a.entry(history).and_modify(|v| *v += price).or_insert(price);
+                let mut history = start.start_history::<N>();
+
+                for _ in history.len()..steps {
+                    let index = history.index();
+                    let (word, bit) = (index / 64, index % 64);
+
+                    // Only count the first appearance of a sequence.
+                    if seen[word] >> bit & 1 == 0 {
+                        seen[word] |= 1 << bit;
+                        table[index] += u16::from(history.price());
+                    }
+
+                    history.advance();
+                }
             }
-            a
+
+            table
         })
-        .and_then(|prices| prices.into_values().max())
-        .expect("no price history")
+        .fold(
+            || vec![0_u32; windows],
+            |mut totals, table| {
+                for (total, price) in totals.iter_mut().zip(table) {
+                    *total += u32::from(price);
+                }
+                totals
+            },
+        )
+        .reduce(
+            || vec![0_u32; windows],
+            |mut a, b| {
+                for (a, b) in a.iter_mut().zip(b) {
+                    *a += b;
+                }
+                a
+            },
+        )
 }
 
 fn parse_input(input: &str) -> Result<Vec<Prng>> {
@@ -187,6 +298,41 @@ mod example {
         assert_eq!(prng.advance(), 5908254);
     }
 
+    #[test]
+    fn skip() {
+        let mut prng = super::Prng { state: 123 };
+
+        assert_eq!(prng.skip(0), 123);
+        assert_eq!(prng.skip(1), 15887950);
+        assert_eq!(prng.skip(9), 5908254);
+
+        let mut prng = super::Prng { state: 1 };
+        let mut stepped = prng;
+
+        for _ in 0..2000 {
+            stepped.advance();
+        }
+
+        assert_eq!(prng.skip(2000), stepped.state);
+    }
+
+    #[test]
+    fn period() {
+        assert_eq!(super::Prng { state: 0 }.period(), 1);
+
+        let mut prng = super::Prng { state: 123 };
+        let period = prng.period();
+
+        assert_eq!(prng.skip(period), 123);
+
+        // The period is minimal, so no proper divisor of it returns to the start.
+        for p in [2, 3, 5, 7, 11, 13, 17, 241] {
+            if period.is_multiple_of(p) {
+                assert_ne!(super::Prng { state: 123 }.skip(period / p), 123);
+            }
+        }
+    }
+
     #[test]
     fn part1() {
         let input = super::parse_input(EXAMPLE1).unwrap();
@@ -200,4 +346,71 @@ mod example {
 
         assert_eq!(super::part2(&input), 23);
     }
+
+    #[test]
+    fn explain() {
+        let input = super::parse_input(EXAMPLE2).unwrap();
+        let explanation = super::explain::explain::<4>(&input, 2000, 3).unwrap();
+
+        assert_eq!(explanation.window, [-2, 1, -1, 3]);
+        assert_eq!(explanation.total, 23);
+
+        let prices = explanation.sales.iter().map(|&(_, price)| price).collect::<Vec<_>>();
+        assert_eq!(prices, [Some(7), Some(7), None, Some(9)]);
+
+        assert_eq!(explanation.alternatives.len(), 3);
+        assert!(explanation.alternatives.iter().all(|&(_, total)| total <= 23));
+
+        let table = explanation.to_string();
+
+        assert!(table.starts_with("best window: -2,1,-1,3 (23 bananas)\n"));
+        assert!(table.contains("         3  never\n"));
+    }
+
+    #[test]
+    fn decode() {
+        for index in [0, 1, 18, 19, 12345, 19_usize.pow(4) - 1] {
+            let window = super::History::<4>::decode(index);
+            let history = super::History { history: window, prev: super::Prng { state: 0 } };
+
+            assert_eq!(history.index(), index);
+        }
+    }
+
+    #[test]
+    fn window_totals() {
+        let input = super::parse_input(EXAMPLE2).unwrap();
+
+        let history = super::History { history: [-2, 1, -1, 3], prev: super::Prng { state: 0 } };
+        let totals = super::window_totals::<4>(&input, 2000);
+
+        assert_eq!(totals.len(), 19_usize.pow(4));
+        assert_eq!(totals[history.index()], 23);
+
+        fn check<const N: usize>(input: &[super::Prng]) {
+            let mut expected = std::collections::HashMap::new();
+
+            for start in input {
+                let mut seen = std::collections::HashSet::new();
+                let mut history = start.start_history::<N>();
+
+                for _ in N..2000 {
+                    if seen.insert(history.history()) {
+                        *expected.entry(history.index()).or_default() += u32::from(history.price());
+                    }
+                    history.advance();
+                }
+            }
+
+            let totals = super::window_totals::<N>(input, 2000);
+
+            for (index, total) in totals.into_iter().enumerate() {
+                assert_eq!(total, expected.get(&index).copied().unwrap_or(0), "window {index}");
+            }
+        }
+
+        check::<1>(&input);
+        check::<2>(&input);
+        check::<3>(&input);
+    }
 }
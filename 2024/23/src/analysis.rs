@@ -0,0 +1,182 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Write};
+
+use petgraph::algo::tarjan_scc;
+
+use crate::dense::{DenseGraph, Set, COMPUTERS};
+use crate::{Computer, Graph};
+
+/// Statistics about the structure of the network.
+#[derive(Clone, Debug)]
+pub struct Analysis {
+    pub nodes: usize,
+    pub edges: usize,
+    /// The number of computers by how many connections they have.
+    pub degrees: BTreeMap<usize, usize>,
+    /// The connected components, largest first.
+    pub components: Vec<Vec<Computer>>,
+    /// Every clique of the largest size, in sorted order.
+    pub maximum_cliques: Vec<Vec<Computer>>,
+    pub triangles: usize,
+    /// The fraction of connected triples that are closed into triangles.
+    pub transitivity: f64,
+    /// The average over all computers of the fraction of their neighbor pairs that are connected.
+    pub average_clustering: f64,
+}
+
+impl Analysis {
+    pub fn new(graph: &Graph) -> Analysis {
+        let dense = DenseGraph::new(graph);
+
+        let mut degrees = BTreeMap::new();
+        for node in graph.nodes() {
+            *degrees.entry(graph.neighbors(node).count()).or_default() += 1;
+        }
+
+        // In an undirected graph, the strongly connected components are the connected ones.
+        let mut components = tarjan_scc(graph);
+        for component in &mut components {
+            component.sort_unstable();
+        }
+        components
+            .sort_unstable_by_key(|component| (std::cmp::Reverse(component.len()), component[0]));
+
+        let mut maximum_cliques = Vec::new();
+        dense.maximal_cliques(|clique| {
+            let size = maximum_cliques.first().map_or(0, |max: &Set| max.len());
+
+            if clique.len() > size {
+                maximum_cliques.clear();
+            }
+            if clique.len() >= size {
+                maximum_cliques.push(clique);
+            }
+        });
+
+        let mut maximum_cliques = maximum_cliques
+            .into_iter()
+            .map(|clique| clique.computers().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        maximum_cliques.sort_unstable();
+
+        let mut triangles = 0;
+        let mut node_triangles = vec![0; COMPUTERS];
+
+        dense.cliques(3, |clique| {
+            triangles += 1;
+            for node in clique.iter() {
+                node_triangles[node] += 1;
+            }
+        });
+
+        let pairs = |degree: usize| (degree * degree.saturating_sub(1) / 2) as f64;
+
+        let triples = degrees.iter().map(|(&degree, &n)| pairs(degree) * n as f64).sum::<f64>();
+        let transitivity = if triples > 0.0 { 3.0 * triangles as f64 / triples } else { 0.0 };
+
+        let average_clustering = graph
+            .nodes()
+            .map(|node| {
+                let pairs = pairs(graph.neighbors(node).count());
+                if pairs > 0.0 {
+                    node_triangles[node.index()] as f64 / pairs
+                } else {
+                    0.0
+                }
+            })
+            .sum::<f64>()
+            / graph.node_count().max(1) as f64;
+
+        Analysis {
+            nodes: graph.node_count(),
+            edges: graph.edge_count(),
+            degrees,
+            components,
+            maximum_cliques,
+            triangles,
+            transitivity,
+            average_clustering,
+        }
+    }
+
+    pub fn clique_number(&self) -> usize {
+        self.maximum_cliques.first().map_or(0, Vec::len)
+    }
+}
+
+fn names(computers: &[Computer]) -> String {
+    computers.iter().map(|v| v.0.as_str()).collect::<Vec<_>>().join(",")
+}
+
+impl fmt::Display for Analysis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} computers, {} connections", self.nodes, self.edges)?;
+        writeln!(f)?;
+        writeln!(f, "{:>8} {:>10}", "degree", "computers")?;
+
+        for (degree, n) in &self.degrees {
+            writeln!(f, "{degree:>8} {n:>10}")?;
+        }
+
+        writeln!(f)?;
+        writeln!(f, "{} connected components", self.components.len())?;
+
+        for component in &self.components {
+            writeln!(
+                f,
+                "    {} computers, starting at {}",
+                component.len(),
+                names(&component[..1])
+            )?;
+        }
+
+        writeln!(f)?;
+        writeln!(f, "clique number: {}", self.clique_number())?;
+
+        for clique in &self.maximum_cliques {
+            writeln!(f, "    {}", names(clique))?;
+        }
+
+        writeln!(f)?;
+        writeln!(f, "triangles: {}", self.triangles)?;
+        writeln!(f, "transitivity: {:.4}", self.transitivity)?;
+        writeln!(f, "average clustering coefficient: {:.4}", self.average_clustering)
+    }
+}
+
+/// Writes the network as an undirected DOT graph, highlighting the computers in `clique` and the
+/// connections between them.
+pub fn write_dot(graph: &Graph, clique: &[Computer], out: &mut impl Write) -> fmt::Result {
+    let clique = clique.iter().collect::<BTreeSet<_>>();
+
+    let mut nodes = graph.nodes().collect::<Vec<_>>();
+    nodes.sort_unstable();
+
+    writeln!(out, "graph network {{")?;
+    writeln!(out, "    node [shape=circle];")?;
+
+    for node in nodes {
+        let name = node.0.as_str();
+
+        if clique.contains(&node) {
+            writeln!(out, "    \"{name}\" [style=filled, fillcolor=gold];")?;
+        } else {
+            writeln!(out, "    \"{name}\";")?;
+        }
+    }
+
+    let mut edges = graph.all_edges().map(|(a, b, ())| (a.min(b), a.max(b))).collect::<Vec<_>>();
+    edges.sort_unstable();
+
+    for (a, b) in edges {
+        let (a_name, b_name) = (a.0.as_str(), b.0.as_str());
+
+        if clique.contains(&a) && clique.contains(&b) {
+            writeln!(out, "    \"{a_name}\" -- \"{b_name}\" [color=red, penwidth=2];")?;
+        } else {
+            writeln!(out, "    \"{a_name}\" -- \"{b_name}\";")?;
+        }
+    }
+
+    writeln!(out, "}}")
+}
@@ -0,0 +1,183 @@
+use std::ops::{BitAnd, BitOr, Not};
+
+use crate::{Computer, Graph};
+
+/// The number of possible computer names, each being two lowercase letters.
+pub const COMPUTERS: usize = 26 * 26;
+
+const WORDS: usize = COMPUTERS.div_ceil(64);
+
+/// A set of computers, stored as a bitset over their dense indices.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Set([u64; WORDS]);
+
+impl Set {
+    pub fn insert(&mut self, index: usize) {
+        self.0[index / 64] |= 1 << (index % 64);
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        self.0[index / 64] &= !(1 << (index % 64));
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// Iterates over the indices in the set, in increasing order.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0.iter().enumerate().flat_map(|(i, &word)| {
+            let mut word = word;
+
+            std::iter::from_fn(move || {
+                if word == 0 {
+                    return None;
+                }
+
+                let bit = word.trailing_zeros() as usize;
+                word &= word - 1;
+
+                Some(i * 64 + bit)
+            })
+        })
+    }
+
+    pub fn computers(&self) -> impl Iterator<Item = Computer> + '_ {
+        self.iter().map(Computer::from_index)
+    }
+}
+
+impl BitAnd for Set {
+    type Output = Set;
+
+    fn bitand(mut self, rhs: Set) -> Set {
+        for (a, b) in self.0.iter_mut().zip(rhs.0) {
+            *a &= b;
+        }
+        self
+    }
+}
+
+impl BitOr for Set {
+    type Output = Set;
+
+    fn bitor(mut self, rhs: Set) -> Set {
+        for (a, b) in self.0.iter_mut().zip(rhs.0) {
+            *a |= b;
+        }
+        self
+    }
+}
+
+impl Not for Set {
+    type Output = Set;
+
+    fn not(mut self) -> Set {
+        for word in &mut self.0 {
+            *word = !*word;
+        }
+        self
+    }
+}
+
+/// The network with its adjacency stored as one bitset per computer.
+#[derive(Clone, Debug)]
+pub struct DenseGraph {
+    nodes: Set,
+    neighbors: Box<[Set; COMPUTERS]>,
+}
+
+impl DenseGraph {
+    pub fn new(graph: &Graph) -> DenseGraph {
+        let mut nodes = Set::default();
+        let mut neighbors = Box::new([Set::default(); COMPUTERS]);
+
+        for node in graph.nodes() {
+            nodes.insert(node.index());
+        }
+
+        for (a, b, ()) in graph.all_edges() {
+            neighbors[a.index()].insert(b.index());
+            neighbors[b.index()].insert(a.index());
+        }
+
+        DenseGraph { nodes, neighbors }
+    }
+
+    /// Orders the nodes by repeatedly taking out one with the fewest remaining neighbors.
+    fn degeneracy_order(&self) -> Vec<usize> {
+        let mut remaining = self.nodes;
+        let mut order = Vec::with_capacity(self.nodes.len());
+
+        while let Some(node) =
+            remaining.iter().min_by_key(|&node| (self.neighbors[node] & remaining).len())
+        {
+            remaining.remove(node);
+            order.push(node);
+        }
+
+        order
+    }
+
+    /// Calls `f` with every maximal clique, using the Bron–Kerbosch algorithm with pivoting.
+    ///
+    /// The top level goes through the nodes in degeneracy order, which keeps the candidate sets
+    /// below it small in sparse graphs.
+    pub fn maximal_cliques(&self, mut f: impl FnMut(Set)) {
+        let mut p = self.nodes;
+        let mut x = Set::default();
+
+        for v in self.degeneracy_order() {
+            let mut r = Set::default();
+            r.insert(v);
+
+            self.bron_kerbosch(r, p & self.neighbors[v], x & self.neighbors[v], &mut f);
+
+            p.remove(v);
+            x.insert(v);
+        }
+    }
+
+    fn bron_kerbosch(&self, r: Set, mut p: Set, mut x: Set, f: &mut impl FnMut(Set)) {
+        // Pick the pivot leaving the fewest candidates to branch on.
+        let Some(pivot) = (p | x).iter().max_by_key(|&u| (p & self.neighbors[u]).len()) else {
+            f(r);
+            return;
+        };
+
+        for v in (p & !self.neighbors[pivot]).iter() {
+            let mut next = r;
+            next.insert(v);
+
+            self.bron_kerbosch(next, p & self.neighbors[v], x & self.neighbors[v], f);
+
+            p.remove(v);
+            x.insert(v);
+        }
+    }
+
+    /// Calls `f` with every clique of exactly `k` nodes, each found once.
+    pub fn cliques(&self, k: usize, mut f: impl FnMut(Set)) {
+        if k > 0 {
+            self.extend_clique(Set::default(), self.nodes, k, &mut f);
+        }
+    }
+
+    fn extend_clique(&self, clique: Set, mut candidates: Set, k: usize, f: &mut impl FnMut(Set)) {
+        let all = candidates;
+
+        for v in all.iter() {
+            // Later candidates only ever extend the clique with higher indices.
+            candidates.remove(v);
+
+            let mut next = clique;
+            next.insert(v);
+
+            if k == 1 {
+                f(next);
+            } else {
+                self.extend_clique(next, candidates & self.neighbors[v], k - 1, f);
+            }
+        }
+    }
+}
@@ -1,143 +1,98 @@
 #![feature(ascii_char)]
 
 use std::ascii;
-use std::collections::HashSet;
 
 use anyhow::{Context, Result};
 use petgraph::prelude::UnGraphMap;
 
+use self::analysis::Analysis;
+use self::dense::DenseGraph;
+
+mod analysis;
+mod dense;
+
 const INPUT: &str = include_str!("./input");
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 struct Computer([ascii::Char; 2]);
 
+impl Computer {
+    /// Returns the dense index of the computer, ordered the same way as the names.
+    fn index(self) -> usize {
+        let [a, b] = self.0.map(|c| (c.to_u8() - b'a') as usize);
+
+        a * 26 + b
+    }
+
+    fn from_index(index: usize) -> Computer {
+        let letter = |n: usize| ascii::Char::from_u8(b'a' + n as u8).expect("letter is ascii");
+
+        Computer([letter(index / 26), letter(index % 26)])
+    }
+}
+
 type Graph = UnGraphMap<Computer, ()>;
 
 fn main() -> Result<()> {
     let graph = parse_input(INPUT).context("failed to parse input")?;
 
-    println!("part 1: {}", part1(&graph));
-    println!("part 2: {}", part2(&graph));
+    match std::env::args().nth(1).as_deref() {
+        None => {
+            println!("part 1: {}", part1(&graph));
+            println!("part 2: {}", part2(&graph));
+        }
+        Some("analyze") => print!("{}", Analysis::new(&graph)),
+        Some("dot") => {
+            let mut out = String::new();
+            analysis::write_dot(&graph, &password_clique(&graph), &mut out)?;
+            print!("{out}");
+        }
+        Some(command) => anyhow::bail!("unknown command: '{command}'"),
+    }
 
     Ok(())
 }
 
 fn part1(graph: &Graph) -> usize {
-    cliques3(graph)
-        .into_iter()
-        .filter(|clique| clique.iter().any(|v| v.0[0].to_u8() == b't'))
-        .count()
-}
-
-fn part2(graph: &Graph) -> String {
-    max_cliques(graph)
-        .into_iter()
-        .reduce(|a, b| if a.len() < b.len() { b } else { a })
-        .map(|clique| {
-            let mut clique = clique.into_iter().collect::<Vec<_>>();
-            clique.sort_unstable();
-
-            let mut s = String::new();
-
-            for v in clique {
-                if !s.is_empty() {
-                    s.push(',');
-                }
-                s.push_str(v.0.as_str());
-            }
-
-            s
-        })
-        .expect("no cliques")
-}
-
-fn cliques3(graph: &Graph) -> Vec<[Computer; 3]> {
-    let mut cliques = Vec::new();
-
-    let mut a_nodes = graph.nodes();
-
-    while let Some(a) = a_nodes.next() {
-        let mut b_nodes = a_nodes.clone();
+    let mut count = 0;
 
-        while let Some(b) = b_nodes.next() {
-            if !graph.contains_edge(a, b) {
-                continue;
-            }
-
-            for c in b_nodes.clone() {
-                if !graph.contains_edge(a, c) || !graph.contains_edge(b, c) {
-                    continue;
-                }
-
-                cliques.push([a, b, c]);
-            }
-        }
-    }
+    DenseGraph::new(graph).cliques(3, |clique| {
+        count += clique.computers().any(|v| v.0[0].to_u8() == b't') as usize;
+    });
 
-    cliques
+    count
 }
 
-fn max_cliques(graph: &Graph) -> Vec<HashSet<Computer>> {
-    // Bron–Kerbosch algorithm: https://en.wikipedia.org/wiki/Bron%E2%80%93Kerbosch_algorithm
-    fn bron_kerbosh(
-        graph: &Graph,
-        cliques: &mut Vec<HashSet<Computer>>,
-        r: HashSet<Computer>,
-        mut p: HashSet<Computer>,
-        mut x: HashSet<Computer>,
-    ) {
-        let Some(&u) = p.union(&x).next() else {
-            cliques.push(r);
-            return;
-        };
+fn part2(graph: &Graph) -> String {
+    password_clique(graph).iter().map(|v| v.0.as_str()).collect::<Vec<_>>().join(",")
+}
 
-        let nodes = {
-            let mut nodes = p.clone();
-            for n in graph.neighbors(u) {
-                nodes.remove(&n);
-            }
-            nodes
-        };
+/// Finds the largest clique, whose sorted names make up the password.
+fn password_clique(graph: &Graph) -> Vec<Computer> {
+    let mut largest = None;
 
-        for v in nodes {
-            let neighbors = graph.neighbors(v).collect();
-
-            bron_kerbosh(
-                graph,
-                cliques,
-                {
-                    let mut r = r.clone();
-                    r.insert(v);
-                    r
-                },
-                p.intersection(&neighbors).copied().collect(),
-                x.intersection(&neighbors).copied().collect(),
-            );
-
-            p.remove(&v);
-            x.insert(v);
+    DenseGraph::new(graph).maximal_cliques(|clique| {
+        if largest.is_none_or(|largest: dense::Set| largest.len() < clique.len()) {
+            largest = Some(clique);
         }
-    }
-
-    let mut cliques = Vec::new();
-
-    bron_kerbosh(graph, &mut cliques, HashSet::new(), graph.nodes().collect(), HashSet::new());
+    });
 
-    cliques
+    largest.expect("no cliques").computers().collect()
 }
 
 fn parse_input(input: &str) -> Result<Graph> {
     let edges = input.lines().map(|line| {
         let (a, b) = line.split_once('-').context("missing '-' in connection")?;
 
-        let a = a
-            .as_ascii()
-            .and_then(|a| a.try_into().ok())
-            .with_context(|| format!("invalid id: {a}"))?;
-        let b = b
-            .as_ascii()
-            .and_then(|a| a.try_into().ok())
-            .with_context(|| format!("invalid id: {b}"))?;
+        let id = |id: &str| {
+            id.as_ascii()
+                .filter(|id| id.iter().all(|c| c.to_char().is_ascii_lowercase()))
+                .and_then(|id| id.try_into().ok())
+                .with_context(|| format!("invalid id: {id}"))
+        };
+
+        let a = id(a)?;
+        let b = id(b)?;
 
         anyhow::Ok((Computer(a), Computer(b)))
     });
@@ -170,4 +125,89 @@ mod example {
 
         assert_eq!(super::part2(&graph), "co,de,ka,ta");
     }
+
+    #[test]
+    fn cliques() {
+        let graph = super::parse_input(EXAMPLE).unwrap();
+        let dense = super::DenseGraph::new(&graph);
+
+        let count = |k| {
+            let mut count = 0;
+            dense.cliques(k, |_| count += 1);
+            count
+        };
+
+        assert_eq!(count(1), graph.node_count());
+        assert_eq!(count(2), graph.edge_count());
+        assert_eq!(count(3), 12);
+        assert_eq!(count(4), 1);
+        assert_eq!(count(5), 0);
+
+        let mut maximal = Vec::new();
+        dense.maximal_cliques(|clique| maximal.push(clique));
+
+        // Every edge and triangle is part of some maximal clique, and none is found twice.
+        assert!(maximal.iter().all(|clique| clique.len() >= 2));
+        for k in [2, 3] {
+            dense.cliques(k, |clique| {
+                assert!(maximal.iter().any(|&m| clique & m == clique), "{k}-clique not covered");
+            });
+        }
+        assert_eq!(maximal.iter().filter(|clique| clique.len() == 4).count(), 1);
+
+        let mut unique =
+            maximal.iter().map(|clique| clique.iter().collect()).collect::<Vec<Vec<_>>>();
+        unique.sort_unstable();
+        unique.dedup();
+
+        assert_eq!(unique.len(), maximal.len());
+    }
+
+    #[test]
+    fn index() {
+        for name in ["aa", "az", "ka", "zz"] {
+            let graph = super::parse_input(&format!("{name}-qp")).unwrap();
+            let computer = graph.nodes().find(|v| v.0.as_str() == name).unwrap();
+
+            assert_eq!(super::Computer::from_index(computer.index()), computer);
+        }
+
+        assert!(super::parse_input("Aa-bb").is_err());
+    }
+
+    #[test]
+    fn analysis() {
+        let graph = super::parse_input(EXAMPLE).unwrap();
+        let analysis = super::Analysis::new(&graph);
+
+        assert_eq!((analysis.nodes, analysis.edges), (16, 32));
+        assert_eq!(analysis.degrees.values().sum::<usize>(), 16);
+        assert_eq!(analysis.degrees.iter().map(|(d, n)| d * n).sum::<usize>(), 64);
+        assert_eq!(analysis.components.len(), 1);
+        assert_eq!(analysis.clique_number(), 4);
+        assert_eq!(analysis.maximum_cliques, [super::password_clique(&graph)]);
+        assert_eq!(analysis.triangles, 12);
+        assert!((0.0..=1.0).contains(&analysis.transitivity));
+        assert!((0.0..=1.0).contains(&analysis.average_clustering));
+
+        let graph = super::parse_input("aa-bb\nbb-cc\ncc-aa\ndd-ee").unwrap();
+        let analysis = super::Analysis::new(&graph);
+
+        assert_eq!(analysis.components.len(), 2);
+        assert_eq!(analysis.maximum_cliques.len(), 1);
+        assert_eq!(analysis.transitivity, 1.0);
+        assert_eq!(analysis.average_clustering, 3.0 / 5.0);
+    }
+
+    #[test]
+    fn dot() {
+        let graph = super::parse_input(EXAMPLE).unwrap();
+
+        let mut dot = String::new();
+        super::analysis::write_dot(&graph, &super::password_clique(&graph), &mut dot).unwrap();
+
+        assert_eq!(dot.matches("fillcolor=gold").count(), 4);
+        assert_eq!(dot.matches("color=red").count(), 6);
+        assert_eq!(dot.matches(" -- ").count(), 32);
+    }
 }
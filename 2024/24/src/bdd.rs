@@ -0,0 +1,226 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{Context, Result};
+
+use crate::repair::Spec;
+use crate::{Gate, GateOp, Wire};
+
+type Node = u32;
+
+const FALSE: Node = 0;
+const TRUE: Node = 1;
+
+/// A reduced ordered binary decision diagram, where equal functions share the same node.
+#[derive(Clone, Debug)]
+struct Bdd {
+    nodes: Vec<(u32, Node, Node)>,
+    unique: HashMap<(u32, Node, Node), Node>,
+    cache: HashMap<(GateOp, Node, Node), Node>,
+}
+
+impl Bdd {
+    fn new() -> Bdd {
+        Bdd {
+            nodes: vec![(u32::MAX, FALSE, FALSE), (u32::MAX, TRUE, TRUE)],
+            unique: HashMap::new(),
+            cache: HashMap::new(),
+        }
+    }
+
+    fn var(&mut self, var: u32) -> Node {
+        self.node(var, FALSE, TRUE)
+    }
+
+    fn node(&mut self, var: u32, lo: Node, hi: Node) -> Node {
+        if lo == hi {
+            return lo;
+        }
+
+        *self.unique.entry((var, lo, hi)).or_insert_with(|| {
+            self.nodes.push((var, lo, hi));
+            (self.nodes.len() - 1) as Node
+        })
+    }
+
+    fn apply(&mut self, op: GateOp, a: Node, b: Node) -> Node {
+        let (a, b) = (a.min(b), a.max(b));
+
+        match (op, a, b) {
+            (GateOp::And, FALSE, _) => return FALSE,
+            (GateOp::And, TRUE, b) => return b,
+            (GateOp::Or, FALSE, b) => return b,
+            (GateOp::Or, TRUE, _) => return TRUE,
+            (GateOp::Xor, FALSE, b) => return b,
+            (GateOp::Xor, a, b) if a == b => return FALSE,
+            (_, a, b) if a == b => return a,
+            _ => {}
+        }
+
+        if let Some(&node) = self.cache.get(&(op, a, b)) {
+            return node;
+        }
+
+        let (var_a, lo_a, hi_a) = self.nodes[a as usize];
+        let (var_b, lo_b, hi_b) = self.nodes[b as usize];
+
+        let var = var_a.min(var_b);
+        let (lo_a, hi_a) = if var_a == var { (lo_a, hi_a) } else { (a, a) };
+        let (lo_b, hi_b) = if var_b == var { (lo_b, hi_b) } else { (b, b) };
+
+        let lo = self.apply(op, lo_a, lo_b);
+        let hi = self.apply(op, hi_a, hi_b);
+        let node = self.node(var, lo, hi);
+
+        self.cache.insert((op, a, b), node);
+
+        node
+    }
+
+    /// Finds an assignment of the variables that makes the node true.
+    fn satisfy(&self, mut node: Node) -> Option<Vec<(u32, bool)>> {
+        let mut assignment = Vec::new();
+
+        while node > TRUE {
+            let (var, lo, hi) = self.nodes[node as usize];
+
+            if lo != FALSE {
+                assignment.push((var, false));
+                node = lo;
+            } else {
+                assignment.push((var, true));
+                node = hi;
+            }
+        }
+
+        (node == TRUE).then_some(assignment)
+    }
+}
+
+/// Inputs for which the circuit disagrees with its specification.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Counterexample {
+    pub x: u64,
+    pub y: u64,
+    pub output: Wire,
+}
+
+struct Builder<'a> {
+    bdd: Bdd,
+    drivers: HashMap<Wire, &'a Gate>,
+    nodes: HashMap<Wire, Node>,
+    visiting: HashSet<Wire>,
+}
+
+impl Builder<'_> {
+    fn wire(&mut self, wire: Wire) -> Result<Node> {
+        if let Some(&node) = self.nodes.get(&wire) {
+            return Ok(node);
+        }
+
+        let node = match self.drivers.get(&wire) {
+            Some(&gate) => {
+                anyhow::ensure!(
+                    self.visiting.insert(wire),
+                    "cycle in circuit through wire '{wire}'"
+                );
+
+                let input1 = self.wire(gate.input1)?;
+                let input2 = self.wire(gate.input2)?;
+
+                self.visiting.remove(&wire);
+                self.bdd.apply(gate.op, input1, input2)
+            }
+            None => {
+                let var = input_var(wire)
+                    .with_context(|| format!("wire '{wire}' has no driver and is not an input"))?;
+
+                self.bdd.var(var)
+            }
+        };
+
+        self.nodes.insert(wire, node);
+
+        Ok(node)
+    }
+}
+
+fn input_var(wire: Wire) -> Option<u32> {
+    let offset = match wire.0[0].to_char() {
+        'x' => 0,
+        'y' => 1,
+        _ => return None,
+    };
+    let bit = wire[1..].parse::<u32>().ok()?;
+
+    Some(bit * 2 + offset)
+}
+
+/// Proves that the circuit computes `spec` for all inputs, or finds inputs where it does not.
+///
+/// The `x` and `y` bits are interleaved in the variable order, which keeps adders linear in size.
+pub fn check(gates: &[Gate], spec: Spec) -> Result<Option<Counterexample>> {
+    let mut builder = Builder {
+        bdd: Bdd::new(),
+        drivers: gates.iter().map(|gate| (gate.output, gate)).collect(),
+        nodes: HashMap::new(),
+        visiting: HashSet::new(),
+    };
+
+    let bits = gates
+        .iter()
+        .flat_map(|gate| [gate.input1, gate.input2])
+        .filter_map(input_var)
+        .map(|var| var / 2 + 1)
+        .max()
+        .unwrap_or(0);
+
+    let outputs = gates
+        .iter()
+        .filter_map(|gate| gate.output.strip_prefix('z')?.parse::<u32>().ok())
+        .max()
+        .map_or(0, |max| max + 1)
+        .max(spec.output_bits(bits as usize) as u32);
+
+    let mut carry = FALSE;
+
+    for i in 0..outputs {
+        let expected = if i < bits {
+            let x = builder.bdd.var(i * 2);
+            let y = builder.bdd.var(i * 2 + 1);
+
+            match spec {
+                Spec::Add => {
+                    let sum = builder.bdd.apply(GateOp::Xor, x, y);
+                    let and = builder.bdd.apply(GateOp::And, x, y);
+                    let carry_and = builder.bdd.apply(GateOp::And, sum, carry);
+
+                    let z = builder.bdd.apply(GateOp::Xor, sum, carry);
+                    carry = builder.bdd.apply(GateOp::Or, and, carry_and);
+                    z
+                }
+                Spec::And => builder.bdd.apply(GateOp::And, x, y),
+            }
+        } else {
+            std::mem::replace(&mut carry, FALSE)
+        };
+
+        let output = Wire::indexed('z', i as usize);
+        let actual =
+            if builder.drivers.contains_key(&output) { builder.wire(output)? } else { FALSE };
+
+        let diff = builder.bdd.apply(GateOp::Xor, expected, actual);
+
+        if let Some(assignment) = builder.bdd.satisfy(diff) {
+            let mut counterexample = Counterexample { x: 0, y: 0, output };
+
+            for (var, value) in assignment {
+                let n = if var % 2 == 0 { &mut counterexample.x } else { &mut counterexample.y };
+                *n |= (value as u64) << (var / 2);
+            }
+
+            return Ok(Some(counterexample));
+        }
+    }
+
+    Ok(None)
+}
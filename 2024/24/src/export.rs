@@ -0,0 +1,96 @@
+use std::collections::BTreeSet;
+use std::fmt::{self, Write};
+
+use crate::{Circuit, Gate, GateOp, Wire};
+
+fn is_port(wire: Wire) -> bool {
+    matches!(wire.0[0].to_char(), 'x' | 'y' | 'z')
+}
+
+/// Writes the gates as a DOT graph, labelling each wire with its value in `circuit`, if any.
+pub fn write_dot(gates: &[Gate], circuit: Option<&Circuit>, out: &mut impl Write) -> fmt::Result {
+    let wires = gates
+        .iter()
+        .flat_map(|gate| [gate.input1, gate.input2, gate.output])
+        .collect::<BTreeSet<_>>();
+
+    writeln!(out, "digraph circuit {{")?;
+    writeln!(out, "    rankdir=LR;")?;
+
+    for wire in wires {
+        let shape = if is_port(wire) { "doublecircle" } else { "circle" };
+
+        match circuit.and_then(|circuit| circuit.get(&wire)) {
+            Some(value) => {
+                let value = value as u8;
+                writeln!(out, "    \"{wire}\" [shape={shape}, label=\"{wire}\\n{value}\"];")?;
+            }
+            None => writeln!(out, "    \"{wire}\" [shape={shape}];")?,
+        }
+    }
+
+    for (i, gate) in gates.iter().enumerate() {
+        let op = match gate.op {
+            GateOp::And => "AND",
+            GateOp::Or => "OR",
+            GateOp::Xor => "XOR",
+        };
+
+        writeln!(out, "    \"gate{i}\" [shape=box, label=\"{op}\"];")?;
+        writeln!(out, "    \"{}\" -> \"gate{i}\";", gate.input1)?;
+        writeln!(out, "    \"{}\" -> \"gate{i}\";", gate.input2)?;
+        writeln!(out, "    \"gate{i}\" -> \"{}\";", gate.output)?;
+    }
+
+    writeln!(out, "}}")
+}
+
+/// Writes the gates as a structural Verilog module, with the `x` and `y` wires as inputs and the
+/// `z` wires as outputs.
+///
+/// Internal wire names are prefixed with `w_`, so they cannot clash with Verilog keywords.
+pub fn write_verilog(gates: &[Gate], out: &mut impl Write) -> fmt::Result {
+    let outputs = gates.iter().map(|gate| gate.output).collect::<BTreeSet<_>>();
+    let inputs = gates
+        .iter()
+        .flat_map(|gate| [gate.input1, gate.input2])
+        .filter(|wire| !outputs.contains(wire))
+        .collect::<BTreeSet<_>>();
+
+    let name = |wire: Wire| if is_port(wire) { wire.to_string() } else { format!("w_{wire}") };
+
+    let ports = inputs
+        .iter()
+        .map(|&wire| format!("input {}", name(wire)))
+        .chain(outputs.iter().filter(|&&wire| is_port(wire)).map(|wire| format!("output {wire}")))
+        .collect::<Vec<_>>();
+
+    writeln!(out, "module circuit(")?;
+    for (i, port) in ports.iter().enumerate() {
+        let sep = if i + 1 < ports.len() { "," } else { "" };
+        writeln!(out, "    {port}{sep}")?;
+    }
+    writeln!(out, ");")?;
+
+    for &wire in outputs.iter().filter(|&&wire| !is_port(wire)) {
+        writeln!(out, "    wire w_{wire};")?;
+    }
+
+    for gate in gates {
+        let op = match gate.op {
+            GateOp::And => "&",
+            GateOp::Or => "|",
+            GateOp::Xor => "^",
+        };
+
+        writeln!(
+            out,
+            "    assign {} = {} {op} {};",
+            name(gate.output),
+            name(gate.input1),
+            name(gate.input2),
+        )?;
+    }
+
+    writeln!(out, "endmodule")
+}
@@ -7,6 +7,13 @@ use std::{ascii, fmt};
 
 use anyhow::{Context, Result};
 
+mod bdd;
+mod export;
+mod parallel;
+mod repair;
+
+use self::repair::Spec;
+
 const INPUT: &str = include_str!("./input");
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -38,6 +45,10 @@ impl Wire {
         Ok((wire, value))
     }
 
+    fn indexed(prefix: char, i: usize) -> Wire {
+        format!("{prefix}{i:02}").parse().expect("indexed wire name is valid")
+    }
+
     fn as_str(&self) -> &str {
         self.0.as_str()
     }
@@ -140,11 +151,23 @@ impl Circuit {
 
     fn emulate(&mut self, gates: &[Gate]) -> u64 {
         let mut queue = VecDeque::from(gates.to_vec());
+        let mut stalled = 0;
 
         while let Some(gate) = queue.pop_front() {
             match gate.try_eval(self) {
-                Some(result) => self.set(gate.output, result),
-                None => queue.push_back(gate),
+                Some(result) => {
+                    self.set(gate.output, result);
+                    stalled = 0;
+                }
+                None => {
+                    queue.push_back(gate);
+                    stalled += 1;
+
+                    // Give up once no gate in the queue can be evaluated, as happens for cycles.
+                    if stalled == queue.len() {
+                        break;
+                    }
+                }
             }
         }
 
@@ -168,68 +191,92 @@ impl Circuit {
 fn main() -> Result<()> {
     let (inputs, gates) = parse_input(INPUT).context("failed to parse input")?;
 
-    println!("part 1: {}", part1(&inputs, &gates));
-    println!("part 2: {}", part2(&inputs, &gates));
+    let mut args = std::env::args().skip(1);
 
-    Ok(())
-}
-
-fn part1(inputs: &HashMap<Wire, bool>, gates: &[Gate]) -> u64 {
-    let mut circuit = Circuit { wires: inputs.clone() };
-
-    circuit.emulate(gates)
-}
-
-fn part2(_inputs: &HashMap<Wire, bool>, gates: &[Gate]) -> String {
-    let highest_z =
-        gates.iter().filter_map(|g| g.output.strip_prefix('z')).max().expect("no z wires found");
-
-    let mut wrong = Vec::new();
-
-    for gate in gates {
-        if gate.op != GateOp::Xor
-            && matches!(gate.output.strip_prefix('z'), Some(n) if n != highest_z)
-        {
-            wrong.push(gate.output);
-            continue;
+    match args.next().as_deref() {
+        None => {
+            println!("part 1: {}", part1(&inputs, &gates));
+            println!("part 2: {}", part2(&gates, Spec::Add, 4));
         }
+        Some("repair") => {
+            let spec = args.next().map_or(Ok(Spec::Add), |spec| spec.parse())?;
+            let max_swaps = match args.next() {
+                Some(n) => n.parse().context("invalid number of swaps")?,
+                None => 4,
+            };
+
+            match repair::faulty_stage(&gates, spec) {
+                None => println!("circuit matches the expected structure"),
+                Some(stage) => println!("first faulty stage: {stage}"),
+            }
 
-        match gate.op {
-            GateOp::Xor => {
-                if !matches!(gate.output.0[0].to_char(), 'x' | 'y' | 'z')
-                    && !matches!(gate.input1.0[0].to_char(), 'x' | 'y' | 'z')
-                    && !matches!(gate.input2.0[0].to_char(), 'x' | 'y' | 'z')
-                {
-                    wrong.push(gate.output);
-                    continue;
+            match repair::repair(&gates, spec, max_swaps) {
+                None => println!("no repair with at most {max_swaps} swaps"),
+                Some(swaps) => {
+                    for (a, b) in swaps {
+                        println!("swap {a} and {b}");
+                    }
                 }
+            }
+        }
+        Some("dot") => {
+            let mut circuit = Circuit { wires: inputs };
+            circuit.emulate(&gates);
 
-                if gates.iter().any(|g| g.has_input(gate.output) && g.op == GateOp::Or) {
-                    wrong.push(gate.output);
+            let mut out = String::new();
+            export::write_dot(&gates, Some(&circuit), &mut out)?;
+            print!("{out}");
+        }
+        Some("verilog") => {
+            let mut out = String::new();
+            export::write_verilog(&gates, &mut out)?;
+            print!("{out}");
+        }
+        Some("verify") => {
+            let spec = args.next().map_or(Ok(Spec::Add), |spec| spec.parse())?;
+
+            let gates = match args.next() {
+                Some(path) => {
+                    let circuit = std::fs::read_to_string(&path)
+                        .with_context(|| format!("failed to read circuit '{path}'"))?;
+                    parse_input(&circuit).context("failed to parse circuit")?.1
                 }
-            }
-            GateOp::And => {
-                if gate.input1 != "x00"
-                    && gate.input2 != "x00"
-                    && gates.iter().any(|g| g.has_input(gate.output) && g.op != GateOp::Or)
-                {
-                    wrong.push(gate.output);
+                None => gates,
+            };
+
+            match bdd::check(&gates, spec)? {
+                None => println!("circuit computes z = {spec}"),
+                Some(bdd::Counterexample { x, y, output }) => {
+                    println!("circuit is wrong at {output} for x = {x}, y = {y}");
                 }
             }
-            _ => {}
         }
+        Some(command) => anyhow::bail!("unknown command: '{command}'"),
     }
 
-    wrong.sort_unstable();
+    Ok(())
+}
+
+fn part1(inputs: &HashMap<Wire, bool>, gates: &[Gate]) -> u64 {
+    let mut circuit = Circuit { wires: inputs.clone() };
+
+    circuit.emulate(gates)
+}
 
-    let mut wrong = wrong.into_iter();
+fn part2(gates: &[Gate], spec: Spec, max_swaps: usize) -> String {
+    let swaps = repair::repair(gates, spec, max_swaps).expect("no swaps repair the circuit");
 
-    match wrong.next() {
+    let mut wires = swaps.into_iter().flat_map(|(a, b)| [a, b]).collect::<Vec<_>>();
+    wires.sort_unstable();
+
+    let mut wires = wires.into_iter();
+
+    match wires.next() {
         None => String::new(),
         Some(first) => {
             let mut s = first.as_str().to_owned();
 
-            for next in wrong {
+            for next in wires {
                 s.push(',');
                 s.push_str(next.as_str());
             }
@@ -302,6 +349,26 @@ mod example2 {
 
         assert_eq!(super::part1(&inputs, &connections), 2024);
     }
+
+    #[test]
+    fn parallel() {
+        let (inputs, connections) = super::parse_input(EXAMPLE).unwrap();
+
+        let mut simulator = super::parallel::Simulator::compile(&connections).unwrap();
+
+        for (&wire, &value) in &inputs {
+            simulator.set(wire, if value { 0b01 } else { 0b10 });
+        }
+        simulator.run();
+
+        let mut inverted =
+            super::Circuit { wires: inputs.iter().map(|(&wire, &value)| (wire, !value)).collect() };
+
+        let z = simulator.get_int('z');
+
+        assert_eq!(z[0], 2024);
+        assert_eq!(z[1], inverted.emulate(&connections));
+    }
 }
 
 #[cfg(test)]
@@ -309,10 +376,161 @@ mod example3 {
     const EXAMPLE: &str = include_str!("./example3");
 
     #[test]
-    #[ignore = "part2 solution is specifically tailored to the question input"]
-    fn part2() {
+    fn export() {
         let (inputs, connections) = super::parse_input(EXAMPLE).unwrap();
 
-        assert_eq!(super::part2(&inputs, &connections), "z00,z01,z02,z05");
+        let mut circuit = super::Circuit { wires: inputs };
+        circuit.emulate(&connections);
+
+        let mut dot = String::new();
+        super::export::write_dot(&connections, Some(&circuit), &mut dot).unwrap();
+
+        assert!(dot.contains("    \"x01\" [shape=doublecircle, label=\"x01\\n1\"];\n"));
+        assert!(dot.contains("    \"gate0\" [shape=box, label=\"AND\"];\n"));
+        assert!(dot.contains("    \"gate0\" -> \"z05\";\n"));
+
+        let mut verilog = String::new();
+        super::export::write_verilog(&connections, &mut verilog).unwrap();
+
+        assert!(verilog.starts_with("module circuit(\n    input x00,\n"));
+        assert!(verilog.contains("    output z05\n);\n"));
+        assert!(verilog.contains("    assign z05 = x00 & y00;\n"));
+        assert!(verilog.ends_with("endmodule\n"));
+    }
+
+    #[test]
+    fn part2() {
+        let (_, connections) = super::parse_input(EXAMPLE).unwrap();
+
+        assert_eq!(super::repair::faulty_stage(&connections, super::Spec::And), Some(0));
+        assert_eq!(super::part2(&connections, super::Spec::And, 2), "z00,z01,z02,z05");
+    }
+
+    #[test]
+    fn check() {
+        let (_, mut connections) = super::parse_input(EXAMPLE).unwrap();
+
+        let counterexample = super::bdd::check(&connections, super::Spec::And).unwrap().unwrap();
+
+        assert_eq!(counterexample.output, "z00");
+        assert_ne!(
+            counterexample.x & counterexample.y & 1,
+            counterexample.x >> 5 & counterexample.y >> 5 & 1
+        );
+
+        for (a, b) in super::repair::repair(&connections, super::Spec::And, 2).unwrap() {
+            for gate in &mut connections {
+                if gate.output == a {
+                    gate.output = b;
+                } else if gate.output == b {
+                    gate.output = a;
+                }
+            }
+        }
+
+        assert_eq!(super::bdd::check(&connections, super::Spec::And).unwrap(), None);
+        assert!(super::bdd::check(&connections, super::Spec::Add).unwrap().is_some());
+    }
+}
+
+#[cfg(test)]
+mod adder {
+    use super::{Gate, GateOp, Spec, Wire};
+
+    fn ripple_carry(bits: usize) -> Vec<Gate> {
+        let gate = |a: Wire, op, b: Wire, output: Wire| Gate { input1: a, input2: b, output, op };
+
+        let mut gates = Vec::new();
+        let mut carry = None;
+
+        for i in 0..bits {
+            let (x, y) = (Wire::indexed('x', i), Wire::indexed('y', i));
+            let z = Wire::indexed('z', i);
+            let (sum, and) = (Wire::indexed('s', i), Wire::indexed('a', i));
+
+            match carry {
+                None => {
+                    gates.push(gate(x, GateOp::Xor, y, z));
+                    gates.push(gate(x, GateOp::And, y, and));
+                    carry = Some(and);
+                }
+                Some(c) => {
+                    let (carry_and, carry_out) = (Wire::indexed('b', i), Wire::indexed('c', i));
+
+                    gates.push(gate(x, GateOp::Xor, y, sum));
+                    gates.push(gate(x, GateOp::And, y, and));
+                    gates.push(gate(sum, GateOp::Xor, c, z));
+                    gates.push(gate(sum, GateOp::And, c, carry_and));
+                    gates.push(gate(and, GateOp::Or, carry_and, carry_out));
+                    carry = Some(carry_out);
+                }
+            }
+        }
+
+        if let Some(last) = gates.last_mut() {
+            last.output = Wire::indexed('z', bits);
+        }
+
+        gates
+    }
+
+    #[test]
+    fn repair() {
+        let mut gates = ripple_carry(8);
+
+        assert_eq!(super::repair::faulty_stage(&gates, Spec::Add), None);
+        assert!(super::repair::simulate(&gates, Spec::Add, 8, 256).unwrap());
+
+        for gate in &mut gates {
+            gate.output = match gate.output.as_str() {
+                "z03" => Wire::indexed('c', 3),
+                "c03" => Wire::indexed('z', 3),
+                "s05" => Wire::indexed('a', 5),
+                "a05" => Wire::indexed('s', 5),
+                _ => continue,
+            };
+        }
+
+        assert_eq!(super::repair::faulty_stage(&gates, Spec::Add), Some(3));
+        assert!(!super::repair::simulate(&gates, Spec::Add, 8, 256).unwrap());
+        assert_eq!(super::part2(&gates, Spec::Add, 2), "a05,c03,s05,z03");
+    }
+
+    #[test]
+    fn lanes() {
+        let gates = ripple_carry(3);
+        let mut simulator = super::parallel::Simulator::compile(&gates).unwrap();
+
+        // Each lane adds a different pair of 3-bit numbers, with the lane index holding both.
+        let bit = |shift| (0..64).fold(0, |lanes, lane: u64| lanes | (lane >> shift & 1) << lane);
+
+        for i in 0..3 {
+            simulator.set(Wire::indexed('x', i), bit(i));
+            simulator.set(Wire::indexed('y', i), bit(i + 3));
+        }
+        simulator.set(Wire::indexed('x', 9), u64::MAX);
+        simulator.run();
+
+        for lane in 0..64 {
+            let sum = (lane & 0b111) + (lane >> 3);
+
+            for i in 0..=3 {
+                let z = simulator.get(Wire::indexed('z', i)).unwrap();
+
+                assert_eq!(z >> lane & 1, sum >> i & 1, "z{i} in lane {lane}");
+            }
+        }
+
+        assert_eq!(simulator.get(Wire::indexed('x', 9)), None);
+    }
+
+    #[test]
+    fn widest() {
+        // A 63-bit adder has a 64-bit sum, which is the most that can be simulated.
+        assert!(super::repair::simulate(&ripple_carry(63), Spec::Add, 63, 256).unwrap());
+        assert!(super::repair::simulate(&ripple_carry(64), Spec::Add, 64, 256).is_err());
+
+        // Wider circuits are still repaired, relying on the proof alone.
+        assert_eq!(super::part2(&ripple_carry(64), Spec::Add, 1), "");
     }
 }
@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+
+use crate::{Gate, GateOp, Wire};
+
+pub const LANES: usize = u64::BITS as usize;
+
+/// A compiled circuit that evaluates 64 input assignments at once, one per bit lane.
+#[derive(Clone, Debug)]
+pub struct Simulator {
+    wires: Vec<Wire>,
+    plan: Vec<(GateOp, usize, usize, usize)>,
+    lanes: Vec<u64>,
+}
+
+impl Simulator {
+    /// Compiles the gates into an evaluation order, with every gate after the gates driving it.
+    pub fn compile(gates: &[Gate]) -> Result<Simulator> {
+        let mut wires = Vec::new();
+        let mut ids = HashMap::new();
+
+        let mut intern = |wire: Wire| {
+            *ids.entry(wire).or_insert_with(|| {
+                wires.push(wire);
+                wires.len() - 1
+            })
+        };
+
+        let gates = gates
+            .iter()
+            .map(|gate| (gate.op, intern(gate.input1), intern(gate.input2), intern(gate.output)))
+            .collect::<Vec<_>>();
+
+        let mut drivers = vec![None; wires.len()];
+
+        for (i, &(_, _, _, output)) in gates.iter().enumerate() {
+            anyhow::ensure!(
+                drivers[output].replace(i).is_none(),
+                "wire '{}' is driven by multiple gates",
+                wires[output],
+            );
+        }
+
+        let mut dependents = vec![Vec::new(); wires.len()];
+        let mut pending = vec![0; gates.len()];
+
+        for (i, &(_, input1, input2, _)) in gates.iter().enumerate() {
+            for input in [input1, input2] {
+                if drivers[input].is_some() {
+                    dependents[input].push(i);
+                    pending[i] += 1;
+                }
+            }
+        }
+
+        let mut ready = (0..gates.len()).filter(|&i| pending[i] == 0).collect::<Vec<_>>();
+        let mut plan = Vec::with_capacity(gates.len());
+
+        while let Some(i) = ready.pop() {
+            plan.push(gates[i]);
+
+            for &dependent in &dependents[gates[i].3] {
+                pending[dependent] -= 1;
+                if pending[dependent] == 0 {
+                    ready.push(dependent);
+                }
+            }
+        }
+
+        if plan.len() < gates.len() {
+            let mut cycle = (0..gates.len())
+                .filter(|&i| pending[i] > 0)
+                .map(|i| wires[gates[i].3])
+                .collect::<Vec<_>>();
+            cycle.sort_unstable();
+
+            let cycle = cycle.iter().map(Wire::as_str).collect::<Vec<_>>();
+
+            anyhow::bail!("cycle in circuit through wires: {}", cycle.join(", "));
+        }
+
+        let lanes = vec![0; wires.len()];
+
+        Ok(Simulator { wires, plan, lanes })
+    }
+
+    /// Sets a wire to `lanes`, which has one bit per lane. Wires not in the circuit are ignored.
+    #[cfg(test)]
+    pub fn set(&mut self, wire: Wire, lanes: u64) {
+        if let Some(id) = self.wires.iter().position(|&w| w == wire) {
+            self.lanes[id] = lanes;
+        }
+    }
+
+    #[cfg(test)]
+    pub fn get(&self, wire: Wire) -> Option<u64> {
+        self.wires.iter().position(|&w| w == wire).map(|id| self.lanes[id])
+    }
+
+    /// Sets the wires starting with `prefix` to the bits of `values`, with one value per lane.
+    pub fn set_int(&mut self, prefix: char, values: &[u64]) {
+        assert!(values.len() <= LANES, "too many values for {LANES} lanes");
+
+        for (id, wire) in self.wires.iter().enumerate() {
+            let Some(bit) = wire_bit(wire, prefix) else { continue };
+
+            self.lanes[id] = values
+                .iter()
+                .enumerate()
+                .fold(0, |lanes, (lane, value)| lanes | (value >> bit & 1) << lane);
+        }
+    }
+
+    /// Reads the wires starting with `prefix` as an integer in each lane.
+    pub fn get_int(&self, prefix: char) -> [u64; LANES] {
+        let mut values = [0; LANES];
+
+        for (id, wire) in self.wires.iter().enumerate() {
+            let Some(bit) = wire_bit(wire, prefix) else { continue };
+
+            for (lane, value) in values.iter_mut().enumerate() {
+                *value |= (self.lanes[id] >> lane & 1) << bit;
+            }
+        }
+
+        values
+    }
+
+    pub fn run(&mut self) {
+        for &(op, input1, input2, output) in &self.plan {
+            let (a, b) = (self.lanes[input1], self.lanes[input2]);
+
+            self.lanes[output] = match op {
+                GateOp::And => a & b,
+                GateOp::Or => a | b,
+                GateOp::Xor => a ^ b,
+            };
+        }
+    }
+}
+
+fn wire_bit(wire: &Wire, prefix: char) -> Option<u32> {
+    wire.strip_prefix(prefix)?.parse().ok().filter(|&bit| bit < u64::BITS)
+}
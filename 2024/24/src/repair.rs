@@ -0,0 +1,283 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{Error, Result};
+
+use crate::bdd;
+use crate::parallel::{Simulator, LANES};
+use crate::{Gate, GateOp, Wire};
+
+/// The function the circuit is meant to compute over its `x` and `y` inputs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Spec {
+    Add,
+    And,
+}
+
+impl FromStr for Spec {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "add" => Ok(Spec::Add),
+            "and" => Ok(Spec::And),
+            _ => anyhow::bail!("unknown spec: '{s}'"),
+        }
+    }
+}
+
+impl fmt::Display for Spec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Spec::Add => write!(f, "x + y"),
+            Spec::And => write!(f, "x & y"),
+        }
+    }
+}
+
+impl Spec {
+    pub fn eval(self, x: u64, y: u64) -> u64 {
+        match self {
+            Spec::Add => x.wrapping_add(y),
+            Spec::And => x & y,
+        }
+    }
+
+    pub fn output_bits(self, input_bits: usize) -> usize {
+        match self {
+            Spec::Add => input_bits + 1,
+            Spec::And => input_bits,
+        }
+    }
+}
+
+struct Netlist {
+    bits: usize,
+    outputs: HashMap<(GateOp, Wire, Wire), Wire>,
+    drivers: HashMap<Wire, usize>,
+}
+
+impl Netlist {
+    fn new(gates: &[Gate], bits: usize) -> Netlist {
+        let outputs = gates
+            .iter()
+            .map(|gate| {
+                let (a, b) = (gate.input1.min(gate.input2), gate.input1.max(gate.input2));
+                ((gate.op, a, b), gate.output)
+            })
+            .collect();
+        let drivers = gates.iter().enumerate().map(|(i, gate)| (gate.output, i)).collect();
+
+        Netlist { bits, outputs, drivers }
+    }
+
+    fn find(&self, op: GateOp, a: Wire, b: Wire) -> Option<Wire> {
+        self.outputs.get(&(op, a.min(b), a.max(b))).copied()
+    }
+
+    /// Matches the netlist stage by stage against a ripple-carry adder, returning the first stage
+    /// that deviates from it.
+    fn verify_add(&self) -> Result<(), usize> {
+        let mut carry = None;
+
+        for i in 0..self.bits {
+            let (x, y, z) = (Wire::indexed('x', i), Wire::indexed('y', i), Wire::indexed('z', i));
+
+            let sum = self.find(GateOp::Xor, x, y).ok_or(i)?;
+            let and = self.find(GateOp::And, x, y).ok_or(i)?;
+
+            carry = Some(match carry {
+                None if sum == z => and,
+                None => return Err(i),
+                Some(carry) => {
+                    self.find(GateOp::Xor, sum, carry).filter(|&out| out == z).ok_or(i)?;
+
+                    let carry_and = self.find(GateOp::And, sum, carry).ok_or(i)?;
+
+                    self.find(GateOp::Or, and, carry_and).ok_or(i)?
+                }
+            });
+        }
+
+        match carry {
+            Some(carry) if carry == Wire::indexed('z', self.bits) => Ok(()),
+            _ => Err(self.bits),
+        }
+    }
+
+    fn verify_and(&self) -> Result<(), usize> {
+        for i in 0..self.bits {
+            let (x, y, z) = (Wire::indexed('x', i), Wire::indexed('y', i), Wire::indexed('z', i));
+
+            if self.find(GateOp::And, x, y) != Some(z) {
+                return Err(i);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn verify(&self, spec: Spec) -> Result<(), usize> {
+        match spec {
+            Spec::Add => self.verify_add(),
+            Spec::And => self.verify_and(),
+        }
+    }
+}
+
+/// Returns the faulty stage of the circuit, or `None` if it matches the expected structure.
+pub fn faulty_stage(gates: &[Gate], spec: Spec) -> Option<usize> {
+    Netlist::new(gates, input_bits(gates)).verify(spec).err()
+}
+
+/// Searches for at most `max_swaps` swaps of gate outputs that make the circuit compute `spec`.
+///
+/// Swaps are only tried between gates around the first faulty stage, and every swap has to move
+/// the faulty stage further along. The repaired circuit is confirmed by randomized simulation, and
+/// then proven equivalent to `spec`.
+pub fn repair(gates: &[Gate], spec: Spec, max_swaps: usize) -> Option<Vec<(Wire, Wire)>> {
+    let bits = input_bits(gates);
+    let mut gates = gates.to_vec();
+    let mut swaps = Vec::new();
+
+    if search(&mut gates, spec, bits, max_swaps, &mut swaps) {
+        Some(swaps)
+    } else {
+        None
+    }
+}
+
+fn search(
+    gates: &mut [Gate],
+    spec: Spec,
+    bits: usize,
+    max_swaps: usize,
+    swaps: &mut Vec<(Wire, Wire)>,
+) -> bool {
+    let netlist = Netlist::new(gates, bits);
+
+    let stage = match netlist.verify(spec) {
+        Ok(()) => {
+            // Circuits too wide to simulate are left to the proof alone.
+            return simulate(gates, spec, bits, 256).unwrap_or(true)
+                && bdd::check(gates, spec).is_ok_and(|counterexample| counterexample.is_none());
+        }
+        Err(_) if swaps.len() == max_swaps => return false,
+        Err(stage) => stage,
+    };
+
+    let candidates = candidates(gates, &netlist, stage).into_iter().collect::<Vec<_>>();
+
+    for (i, &a) in candidates.iter().enumerate() {
+        for &b in &candidates[i + 1..] {
+            swap_outputs(gates, a, b);
+
+            let advanced = match Netlist::new(gates, bits).verify(spec) {
+                Ok(()) => true,
+                Err(next) => next > stage,
+            };
+
+            if advanced {
+                swaps.push((gates[a].output, gates[b].output));
+
+                if search(gates, spec, bits, max_swaps, swaps) {
+                    return true;
+                }
+
+                swaps.pop();
+            }
+
+            swap_outputs(gates, a, b);
+        }
+    }
+
+    false
+}
+
+fn swap_outputs(gates: &mut [Gate], a: usize, b: usize) {
+    let output = gates[a].output;
+    gates[a].output = gates[b].output;
+    gates[b].output = output;
+}
+
+/// Collects the gates of the full adder at `stage`, along with the gates driving its inputs and
+/// outputs.
+fn candidates(gates: &[Gate], netlist: &Netlist, stage: usize) -> BTreeSet<usize> {
+    let inputs = [Wire::indexed('x', stage), Wire::indexed('y', stage)];
+
+    let first = gates
+        .iter()
+        .enumerate()
+        .filter(|(_, gate)| inputs.iter().any(|&wire| gate.has_input(wire)))
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+
+    let second = gates
+        .iter()
+        .enumerate()
+        .filter(|(_, gate)| first.iter().any(|&i| gate.has_input(gates[i].output)))
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+
+    let drivers = second
+        .iter()
+        .flat_map(|&i| [gates[i].input1, gates[i].input2])
+        .chain([Wire::indexed('z', stage)])
+        .chain(stage.checked_sub(1).map(|prev| Wire::indexed('z', prev)))
+        .filter_map(|wire| netlist.drivers.get(&wire).copied());
+
+    first.iter().chain(&second).copied().chain(drivers).collect()
+}
+
+fn input_bits(gates: &[Gate]) -> usize {
+    gates
+        .iter()
+        .flat_map(|gate| [gate.input1, gate.input2])
+        .filter(|wire| wire.starts_with('x'))
+        .collect::<BTreeSet<_>>()
+        .len()
+}
+
+/// Checks the circuit against `spec` on random inputs, rounding the trials up to whole passes of
+/// the bit-parallel simulator.
+///
+/// Fails if the output is wider than the 64 bits the simulator reads into each lane.
+pub fn simulate(gates: &[Gate], spec: Spec, bits: usize, trials: usize) -> Result<bool> {
+    let output_bits = spec.output_bits(bits);
+
+    anyhow::ensure!(
+        output_bits <= u64::BITS as usize,
+        "{bits}-bit inputs give {output_bits} output bits, more than can be simulated",
+    );
+
+    let Ok(mut simulator) = Simulator::compile(gates) else { return Ok(false) };
+
+    let mask = |bits: usize| u64::MAX.checked_shr(u64::BITS - bits as u32).unwrap_or(0);
+
+    let input_mask = mask(bits);
+    let output_mask = mask(output_bits);
+
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    let mut random = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state & input_mask
+    };
+
+    let correct = (0..trials.div_ceil(LANES)).all(|_| {
+        let x = (0..LANES).map(|_| random()).collect::<Vec<_>>();
+        let y = (0..LANES).map(|_| random()).collect::<Vec<_>>();
+
+        simulator.set_int('x', &x);
+        simulator.set_int('y', &y);
+        simulator.run();
+
+        let z = simulator.get_int('z');
+
+        (0..LANES).all(|lane| z[lane] == spec.eval(x[lane], y[lane]) & output_mask)
+    });
+
+    Ok(correct)
+}
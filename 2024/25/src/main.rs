@@ -2,48 +2,146 @@ use anyhow::{Context, Result};
 
 const INPUT: &str = include_str!("./input");
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
-struct Lock([u8; 5]);
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Lock(Vec<u8>);
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
-struct Key([u8; 5]);
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Key(Vec<u8>);
 
 impl Key {
-    fn check(&self, lock: &Lock) -> bool {
-        self.0.into_iter().zip(lock.0).all(|(a, b)| a + b <= 5)
+    /// Checks whether the key fits the lock, with `height` rows of space between them.
+    #[cfg(test)]
+    fn check(&self, lock: &Lock, height: u8) -> bool {
+        self.0.iter().zip(&lock.0).all(|(a, b)| a + b <= height)
+    }
+}
+
+/// The locks and keys, along with the number of pins and the space for them, which are the same
+/// for every schematic.
+#[derive(Clone, Debug)]
+struct Schematics {
+    width: usize,
+    height: u8,
+    locks: Vec<Lock>,
+    keys: Vec<Key>,
+}
+
+/// The keys that fit next to each pin height in each column, as bitsets over the keys.
+///
+/// A lock then fits the keys in the intersection of the sets for its pins.
+#[derive(Clone, Debug)]
+struct FitIndex {
+    words: usize,
+    buckets: Vec<Vec<Vec<u64>>>,
+}
+
+impl FitIndex {
+    fn new(schematics: &Schematics) -> FitIndex {
+        let Schematics { width, height, ref keys, .. } = *schematics;
+
+        let words = keys.len().div_ceil(64);
+        let mut buckets = vec![vec![vec![0; words]; usize::from(height) + 1]; width];
+
+        for (column, buckets) in buckets.iter_mut().enumerate() {
+            for (i, key) in keys.iter().enumerate() {
+                // The key fits next to every pin that leaves enough space for its cut.
+                for bucket in &mut buckets[..=usize::from(height - key.0[column])] {
+                    bucket[i / 64] |= 1 << (i % 64);
+                }
+            }
+        }
+
+        FitIndex { words, buckets }
+    }
+
+    /// Returns the keys that fit the lock, as a bitset.
+    fn fitting(&self, lock: &Lock) -> Vec<u64> {
+        let mut keys = vec![u64::MAX; self.words];
+
+        for (buckets, &pin) in self.buckets.iter().zip(&lock.0) {
+            for (keys, bucket) in keys.iter_mut().zip(&buckets[usize::from(pin)]) {
+                *keys &= bucket;
+            }
+        }
+
+        keys
     }
 }
 
 fn main() -> Result<()> {
-    let (locks, keys) = parse_input(INPUT).context("failed to parse input")?;
+    let schematics = parse_input(INPUT).context("failed to parse input")?;
 
-    println!("part 1: {}", part1(&locks, &keys));
-    println!("part 2 is free");
+    match std::env::args().nth(1).as_deref() {
+        None => {
+            println!("part 1: {}", part1(&schematics));
+            println!("part 2 is free");
+        }
+        Some("report") => {
+            let heights =
+                |heights: &[u8]| heights.iter().map(u8::to_string).collect::<Vec<_>>().join(",");
+
+            println!(
+                "{} locks and {} keys with {} pins of up to {}",
+                schematics.locks.len(),
+                schematics.keys.len(),
+                schematics.width,
+                schematics.height,
+            );
+
+            for (lock, keys) in schematics.locks.iter().zip(fits(&schematics)) {
+                let keys = keys.iter().map(usize::to_string).collect::<Vec<_>>();
+
+                println!("lock {}: keys {}", heights(&lock.0), keys.join(", "));
+            }
+        }
+        Some(command) => anyhow::bail!("unknown command: '{command}'"),
+    }
 
     Ok(())
 }
 
-fn part1(locks: &[Lock], keys: &[Key]) -> usize {
-    keys.iter().map(|key| locks.iter().filter(|lock| key.check(lock)).count()).sum()
+fn part1(schematics: &Schematics) -> usize {
+    let index = FitIndex::new(schematics);
+
+    schematics
+        .locks
+        .iter()
+        .map(|lock| {
+            index.fitting(lock).iter().map(|word| word.count_ones() as usize).sum::<usize>()
+        })
+        .sum()
+}
+
+/// Lists the indices of the keys that fit each lock.
+fn fits(schematics: &Schematics) -> Vec<Vec<usize>> {
+    let index = FitIndex::new(schematics);
+
+    schematics
+        .locks
+        .iter()
+        .map(|lock| {
+            let keys = index.fitting(lock);
+
+            (0..schematics.keys.len()).filter(|&i| keys[i / 64] >> (i % 64) & 1 == 1).collect()
+        })
+        .collect()
 }
 
-fn parse_input(input: &str) -> Result<(Vec<Lock>, Vec<Key>)> {
+fn parse_input(input: &str) -> Result<Schematics> {
     enum LockOrKey {
         Lock(Lock),
         Key(Key),
     }
 
-    fn parse_lock_or_key(chunk: &str) -> Option<LockOrKey> {
-        const PINS: usize = 5;
-        const HEIGHT: usize = 5;
-
-        let mut heights = [0; PINS];
+    /// Parses a schematic with `width` pins and `height` rows between the top and bottom row.
+    fn parse_lock_or_key(chunk: &str, width: usize, height: usize) -> Option<LockOrKey> {
+        let mut heights = vec![0; width];
         let mut lines = chunk.lines();
 
         let top = lines.next()?;
 
-        for row in lines.by_ref().take(HEIGHT) {
-            if row.len() > PINS {
+        for row in lines.by_ref().take(height) {
+            if row.len() != width {
                 return None;
             }
 
@@ -58,31 +156,43 @@ fn parse_input(input: &str) -> Result<(Vec<Lock>, Vec<Key>)> {
 
         let bottom = lines.next()?;
 
-        if lines.next().is_some() {
+        if lines.next().is_some() || top.len() != width || bottom.len() != width {
             return None;
         }
 
-        if top == "#####" && bottom == "....." {
+        let is_full = |row: &str| row.chars().all(|c| c == '#');
+        let is_empty = |row: &str| row.chars().all(|c| c == '.');
+
+        if is_full(top) && is_empty(bottom) {
             Some(LockOrKey::Lock(Lock(heights)))
-        } else if top == "....." && bottom == "#####" {
+        } else if is_empty(top) && is_full(bottom) {
             Some(LockOrKey::Key(Key(heights)))
         } else {
             None
         }
     }
 
+    let mut chunks = input.split("\n\n").peekable();
+
+    let first = chunks.peek().context("no schematics")?;
+    let width = first.lines().next().map_or(0, str::len);
+    let height = first.lines().count().checked_sub(2).context("schematic has too few rows")?;
+
+    anyhow::ensure!(width > 0, "schematic has no pins");
+    let height = u8::try_from(height).context("schematic has too many rows")?;
+
     let mut locks = Vec::new();
     let mut keys = Vec::new();
 
-    for chunk in input.split("\n\n") {
-        match parse_lock_or_key(chunk) {
+    for chunk in chunks {
+        match parse_lock_or_key(chunk, width, usize::from(height)) {
             Some(LockOrKey::Lock(lock)) => locks.push(lock),
             Some(LockOrKey::Key(key)) => keys.push(key),
-            None => anyhow::bail!("invalid lock or key:\n\n{chunk}"),
+            None => anyhow::bail!("invalid {width}x{height} lock or key:\n\n{chunk}"),
         }
     }
 
-    Ok((locks, keys))
+    Ok(Schematics { width, height, locks, keys })
 }
 
 #[cfg(test)]
@@ -91,8 +201,34 @@ mod example {
 
     #[test]
     fn part1() {
-        let (locks, keys) = super::parse_input(EXAMPLE).unwrap();
+        let schematics = super::parse_input(EXAMPLE).unwrap();
+
+        assert_eq!(super::part1(&schematics), 3);
+    }
+
+    #[test]
+    fn fits() {
+        let schematics = super::parse_input(EXAMPLE).unwrap();
+
+        assert_eq!((schematics.width, schematics.height), (5, 5));
+        assert_eq!(super::fits(&schematics), [vec![2], vec![1, 2]]);
+
+        for (lock, keys) in schematics.locks.iter().zip(super::fits(&schematics)) {
+            for (i, key) in schematics.keys.iter().enumerate() {
+                assert_eq!(key.check(lock, schematics.height), keys.contains(&i));
+            }
+        }
+    }
+
+    #[test]
+    fn sizes() {
+        let schematics = super::parse_input("###\n.#.\n...\n...\n\n...\n...\n#..\n###").unwrap();
+
+        assert_eq!((schematics.width, schematics.height), (3, 2));
+        assert_eq!(schematics.locks, [super::Lock(vec![0, 1, 0])]);
+        assert_eq!(schematics.keys, [super::Key(vec![1, 0, 0])]);
+        assert_eq!(super::part1(&schematics), 1);
 
-        assert_eq!(super::part1(&locks, &keys), 3);
+        assert!(super::parse_input("###\n.#.\n...\n\n....\n#...\n####").is_err());
     }
 }
@@ -0,0 +1,372 @@
+use std::fmt;
+use std::ops::{Add, BitAnd, BitOr, BitXor, Mul, Not, Shl, Shr, Sub};
+use std::str::FromStr;
+
+use anyhow::Error;
+
+/// An unsigned integer of `BITS` bits, where `BITS` is between 1 and 64.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[repr(transparent)]
+pub struct UInt<const BITS: u32>(u64);
+
+/// Creates a [`UInt`] from a literal, failing to compile if it overflows the type.
+#[macro_export]
+macro_rules! uint {
+    ($t:ty, $v:literal) => {
+        const { <$t>::from_literal($v) }
+    };
+}
+
+impl<const BITS: u32> UInt<BITS> {
+    pub const BITS: u32 = BITS;
+
+    const MASK: u64 = {
+        assert!(BITS > 0 && BITS <= u64::BITS, "unsupported bit width");
+        u64::MAX >> (u64::BITS - BITS)
+    };
+
+    pub const MIN: Self = Self(0);
+    pub const MAX: Self = Self(Self::MASK);
+
+    pub const fn new(n: u64) -> Option<Self> {
+        if n <= Self::MASK {
+            Some(Self(n))
+        } else {
+            None
+        }
+    }
+
+    pub const fn from_literal(n: u64) -> Self {
+        match Self::new(n) {
+            Some(v) => v,
+            None => panic!("integer literal overflows target type"),
+        }
+    }
+
+    pub const fn from_u64(n: u64) -> Self {
+        Self(n & Self::MASK)
+    }
+
+    pub const fn to_u64(self) -> u64 {
+        self.0
+    }
+
+    pub const fn checked_add(self, rhs: Self) -> Option<Self> {
+        match self.0.checked_add(rhs.0) {
+            Some(n) => Self::new(n),
+            None => None,
+        }
+    }
+
+    pub const fn checked_sub(self, rhs: Self) -> Option<Self> {
+        match self.0.checked_sub(rhs.0) {
+            Some(n) => Some(Self(n)),
+            None => None,
+        }
+    }
+
+    pub const fn checked_mul(self, rhs: Self) -> Option<Self> {
+        match self.0.checked_mul(rhs.0) {
+            Some(n) => Self::new(n),
+            None => None,
+        }
+    }
+
+    pub const fn checked_shl(self, rhs: u32) -> Option<Self> {
+        if rhs < BITS {
+            Some(Self::from_u64(self.0 << rhs))
+        } else {
+            None
+        }
+    }
+
+    pub const fn checked_shr(self, rhs: u32) -> Option<Self> {
+        if rhs < BITS {
+            Some(Self(self.0 >> rhs))
+        } else {
+            None
+        }
+    }
+
+    pub const fn wrapping_add(self, rhs: Self) -> Self {
+        Self::from_u64(self.0.wrapping_add(rhs.0))
+    }
+
+    pub const fn wrapping_sub(self, rhs: Self) -> Self {
+        Self::from_u64(self.0.wrapping_sub(rhs.0))
+    }
+
+    pub const fn wrapping_mul(self, rhs: Self) -> Self {
+        Self::from_u64(self.0.wrapping_mul(rhs.0))
+    }
+
+    pub const fn wrapping_shl(self, rhs: u32) -> Self {
+        Self::from_u64(self.0 << (rhs % BITS))
+    }
+
+    pub const fn wrapping_shr(self, rhs: u32) -> Self {
+        Self(self.0 >> (rhs % BITS))
+    }
+
+    pub const fn saturating_add(self, rhs: Self) -> Self {
+        match self.checked_add(rhs) {
+            Some(v) => v,
+            None => Self::MAX,
+        }
+    }
+
+    pub const fn saturating_sub(self, rhs: Self) -> Self {
+        match self.checked_sub(rhs) {
+            Some(v) => v,
+            None => Self::MIN,
+        }
+    }
+
+    pub const fn saturating_mul(self, rhs: Self) -> Self {
+        match self.checked_mul(rhs) {
+            Some(v) => v,
+            None => Self::MAX,
+        }
+    }
+
+    fn shift_amount(rhs: Self) -> Option<u32> {
+        u32::try_from(rhs.0).ok()
+    }
+}
+
+impl<const BITS: u32> BitOr for UInt<BITS> {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl<const BITS: u32> BitAnd for UInt<BITS> {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        Self(self.0 & rhs.0)
+    }
+}
+
+impl<const BITS: u32> BitXor for UInt<BITS> {
+    type Output = Self;
+
+    fn bitxor(self, rhs: Self) -> Self::Output {
+        Self(self.0 ^ rhs.0)
+    }
+}
+
+impl<const BITS: u32> Not for UInt<BITS> {
+    type Output = Self;
+
+    fn not(self) -> Self::Output {
+        Self::from_u64(!self.0)
+    }
+}
+
+/// Shifts left, giving zero when shifting by `BITS` or more, as every bit is shifted out.
+impl<const BITS: u32> Shl for UInt<BITS> {
+    type Output = Self;
+
+    fn shl(self, rhs: Self) -> Self::Output {
+        Self::shift_amount(rhs).and_then(|rhs| self.checked_shl(rhs)).unwrap_or(Self::MIN)
+    }
+}
+
+/// Shifts right, giving zero when shifting by `BITS` or more, as every bit is shifted out.
+impl<const BITS: u32> Shr for UInt<BITS> {
+    type Output = Self;
+
+    fn shr(self, rhs: Self) -> Self::Output {
+        Self::shift_amount(rhs).and_then(|rhs| self.checked_shr(rhs)).unwrap_or(Self::MIN)
+    }
+}
+
+impl<const BITS: u32> Add for UInt<BITS> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        self.wrapping_add(rhs)
+    }
+}
+
+impl<const BITS: u32> Sub for UInt<BITS> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.wrapping_sub(rhs)
+    }
+}
+
+impl<const BITS: u32> Mul for UInt<BITS> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        self.wrapping_mul(rhs)
+    }
+}
+
+impl<const BITS: u32> FromStr for UInt<BITS> {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (digits, radix) = if let Some(s) = s.strip_prefix("0b") {
+            (s, 2)
+        } else if let Some(s) = s.strip_prefix("0o") {
+            (s, 8)
+        } else if let Some(s) = s.strip_prefix("0x") {
+            (s, 16)
+        } else {
+            (s, 10)
+        };
+
+        let v = u64::from_str_radix(digits, radix)?;
+
+        Self::new(v).ok_or_else(|| anyhow::anyhow!("number too large to fit in target type"))
+    }
+}
+
+impl<const BITS: u32> fmt::Display for UInt<BITS> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl<const BITS: u32> fmt::Binary for UInt<BITS> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Binary::fmt(&self.0, f)
+    }
+}
+
+impl<const BITS: u32> fmt::Octal for UInt<BITS> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Octal::fmt(&self.0, f)
+    }
+}
+
+impl<const BITS: u32> fmt::LowerHex for UInt<BITS> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.0, f)
+    }
+}
+
+impl<const BITS: u32> fmt::UpperHex for UInt<BITS> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::UpperHex::fmt(&self.0, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UInt;
+
+    type U3 = UInt<3>;
+    type U64 = UInt<64>;
+
+    #[test]
+    fn bounds() {
+        assert_eq!(U3::MAX.to_u64(), 7);
+        assert_eq!(U64::MAX.to_u64(), u64::MAX);
+        assert_eq!(UInt::<1>::MAX.to_u64(), 1);
+
+        assert_eq!(U3::new(7), Some(uint!(U3, 7)));
+        assert_eq!(U3::new(8), None);
+        assert_eq!(U3::from_u64(13), uint!(U3, 5));
+        assert_eq!(U64::new(u64::MAX), Some(U64::MAX));
+    }
+
+    #[test]
+    fn checked() {
+        assert_eq!(uint!(U3, 3).checked_add(uint!(U3, 4)), Some(U3::MAX));
+        assert_eq!(uint!(U3, 4).checked_add(uint!(U3, 4)), None);
+        assert_eq!(U3::MIN.checked_sub(uint!(U3, 1)), None);
+        assert_eq!(uint!(U3, 3).checked_mul(uint!(U3, 2)), Some(uint!(U3, 6)));
+        assert_eq!(uint!(U3, 3).checked_mul(uint!(U3, 3)), None);
+        assert_eq!(uint!(U3, 1).checked_shl(2), Some(uint!(U3, 4)));
+        assert_eq!(uint!(U3, 5).checked_shl(1), Some(uint!(U3, 2)));
+        assert_eq!(uint!(U3, 1).checked_shl(3), None);
+        assert_eq!(U3::MAX.checked_shr(3), None);
+
+        assert_eq!(U64::MAX.checked_add(uint!(U64, 1)), None);
+        assert_eq!(U64::MAX.checked_mul(uint!(U64, 2)), None);
+        assert_eq!(U64::MAX.checked_shl(63).map(UInt::to_u64), Some(1 << 63));
+        assert_eq!(U64::MAX.checked_shl(64), None);
+        assert_eq!(U64::MAX.checked_shr(64), None);
+    }
+
+    #[test]
+    fn wrapping() {
+        assert_eq!(U3::MAX.wrapping_add(uint!(U3, 1)), U3::MIN);
+        assert_eq!(U3::MIN.wrapping_sub(uint!(U3, 1)), U3::MAX);
+        assert_eq!(uint!(U3, 5).wrapping_mul(uint!(U3, 3)), uint!(U3, 7));
+        assert_eq!(uint!(U3, 1).wrapping_shl(4), uint!(U3, 2));
+        assert_eq!(uint!(U3, 4).wrapping_shr(5), uint!(U3, 1));
+        assert_eq!(!uint!(U3, 5), uint!(U3, 2));
+
+        assert_eq!(U64::MAX.wrapping_add(uint!(U64, 1)), U64::MIN);
+        assert_eq!(U64::MIN.wrapping_sub(uint!(U64, 1)), U64::MAX);
+        assert_eq!(uint!(U64, 1).wrapping_shl(65), uint!(U64, 2));
+        assert_eq!(!U64::MIN, U64::MAX);
+
+        assert_eq!(U3::MAX + uint!(U3, 2), uint!(U3, 1));
+        assert_eq!(U3::MIN - uint!(U3, 2), uint!(U3, 6));
+        assert_eq!(uint!(U3, 6) * uint!(U3, 6), uint!(U3, 4));
+    }
+
+    #[test]
+    fn saturating() {
+        assert_eq!(uint!(U3, 6).saturating_add(uint!(U3, 6)), U3::MAX);
+        assert_eq!(uint!(U3, 1).saturating_sub(uint!(U3, 6)), U3::MIN);
+        assert_eq!(uint!(U3, 4).saturating_mul(uint!(U3, 2)), U3::MAX);
+        assert_eq!(uint!(U3, 2).saturating_add(uint!(U3, 3)), uint!(U3, 5));
+
+        assert_eq!(U64::MAX.saturating_add(uint!(U64, 1)), U64::MAX);
+        assert_eq!(U64::MIN.saturating_sub(uint!(U64, 1)), U64::MIN);
+        assert_eq!(U64::MAX.saturating_mul(uint!(U64, 2)), U64::MAX);
+    }
+
+    #[test]
+    fn shifts() {
+        assert_eq!(uint!(U3, 3) << uint!(U3, 1), uint!(U3, 6));
+        assert_eq!(uint!(U3, 6) >> uint!(U3, 2), uint!(U3, 1));
+
+        // Shifting out every bit leaves nothing, rather than wrapping the shift amount.
+        assert_eq!(U3::MAX << uint!(U3, 3), U3::MIN);
+        assert_eq!(U3::MAX >> uint!(U3, 7), U3::MIN);
+        assert_eq!(U64::MAX << uint!(U64, 64), U64::MIN);
+        assert_eq!(U64::MAX >> U64::MAX, U64::MIN);
+        assert_eq!(U64::MAX >> uint!(U64, 63), uint!(U64, 1));
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!("5".parse::<U3>().unwrap(), uint!(U3, 5));
+        assert_eq!("0b101".parse::<U3>().unwrap(), uint!(U3, 5));
+        assert_eq!("0o7".parse::<U3>().unwrap(), uint!(U3, 7));
+        assert_eq!("0xff".parse::<UInt<8>>().unwrap(), UInt::<8>::MAX);
+        assert_eq!("0xffffffffffffffff".parse::<U64>().unwrap(), U64::MAX);
+
+        assert!("8".parse::<U3>().is_err());
+        assert!("0b1000".parse::<U3>().is_err());
+        assert!("0o10".parse::<U3>().is_err());
+        assert!("0x100".parse::<UInt<8>>().is_err());
+        assert!("18446744073709551616".parse::<U64>().is_err());
+        assert!("0b2".parse::<U3>().is_err());
+        assert!("".parse::<U3>().is_err());
+    }
+
+    #[test]
+    fn format() {
+        let v = uint!(UInt<8>, 202);
+
+        assert_eq!(v.to_string(), "202");
+        assert_eq!(format!("{v:b}"), "11001010");
+        assert_eq!(format!("{v:#o}"), "0o312");
+        assert_eq!(format!("{v:x}"), "ca");
+        assert_eq!(format!("{v:#X}"), "0xCA");
+        assert_eq!(format!("{:>4}", uint!(U3, 7)), "   7");
+        assert_eq!(format!("{:x}", U64::MAX), "ffffffffffffffff");
+    }
+}